@@ -0,0 +1,41 @@
+use gloo_utils::{document, window};
+
+const LIVE_REGION_ID: &str = "pmg-live-region";
+
+/// Announces `message` to screen readers via a visually hidden live region.
+///
+/// Snackbars vanish after a few seconds and are not reliably read out, so
+/// action results are additionally announced here.
+pub(crate) fn announce(message: &str) {
+    let document = document();
+    let region = match document.get_element_by_id(LIVE_REGION_ID) {
+        Some(region) => region,
+        None => {
+            let Ok(region) = document.create_element("div") else {
+                return;
+            };
+            region.set_id(LIVE_REGION_ID);
+            let _ = region.set_attribute("role", "status");
+            let _ = region.set_attribute("aria-live", "polite");
+            let _ = region.set_attribute(
+                "style",
+                "position: absolute; width: 1px; height: 1px; overflow: hidden; \
+                 clip: rect(0 0 0 0); white-space: nowrap;",
+            );
+            if let Some(body) = document.body() {
+                let _ = body.append_child(&region);
+            }
+            region
+        }
+    };
+    region.set_text_content(Some(message));
+}
+
+/// Whether the system asks for reduced motion.
+pub(crate) fn prefers_reduced_motion() -> bool {
+    window()
+        .match_media("(prefers-reduced-motion: reduce)")
+        .ok()
+        .flatten()
+        .is_some_and(|query| query.matches())
+}
@@ -0,0 +1,215 @@
+use pwt::css::{ColorScheme, JustifyContent};
+use pwt::prelude::*;
+use pwt::touch::{SideDialog, SideDialogLocation};
+use pwt::widget::{Button, Column, Container, Dialog, Row};
+
+use crate::spam_list::MailInfo;
+use crate::MailAction;
+
+/// Bottom sheet listing all actions for a mail.
+///
+/// This is the alternative to the swipe actions of the list rows, reachable
+/// via long-press, context menu or the rows' "more" button.
+pub(crate) fn action_sheet(
+    mail: &MailInfo,
+    on_preview: Option<Callback<()>>,
+    on_sender: Callback<()>,
+    on_action: Callback<MailAction>,
+    on_close: Callback<()>,
+) -> SideDialog {
+    let mut actions = Column::new()
+        .padding(2)
+        .gap(1)
+        .class(JustifyContent::Stretch)
+        .attribute("role", "menu")
+        .attribute("aria-label", tr!("Actions for mail from {0}", mail.from))
+        .with_child(
+            Container::new()
+                .class("pwt-font-title-small pwt-text-truncate")
+                .padding_bottom(1)
+                .with_child(&mail.subject),
+        );
+
+    if let Some(on_preview) = on_preview {
+        actions.add_child(
+            Button::new(tr!("Open"))
+                .icon_class("fa fa-envelope-open-o")
+                .class("pwt-button-text")
+                .attribute("role", "menuitem")
+                .on_activate({
+                    let on_close = on_close.clone();
+                    move |_| {
+                        on_close.emit(());
+                        on_preview.emit(());
+                    }
+                }),
+        );
+    }
+
+    actions.add_child(
+        Button::new(tr!("Sender Details"))
+            .icon_class("fa fa-user")
+            .class("pwt-button-text")
+            .attribute("role", "menuitem")
+            .on_activate({
+                let on_close = on_close.clone();
+                move |_| {
+                    on_close.emit(());
+                    on_sender.emit(());
+                }
+            }),
+    );
+
+    add_mail_actions(&mut actions, mail.seen, on_action, on_close.clone());
+
+    SideDialog::new()
+        .location(SideDialogLocation::Bottom)
+        .on_close(on_close)
+        .with_child(actions)
+}
+
+// the actions for a single mail, each closing the sheet first
+fn add_mail_actions(
+    actions: &mut Column,
+    seen: bool,
+    on_action: Callback<MailAction>,
+    on_close: Callback<()>,
+) {
+    let seen_action = if seen {
+        MailAction::MarkUnseen
+    } else {
+        MailAction::MarkSeen
+    };
+
+    for action in [
+        MailAction::Deliver,
+        MailAction::Welcomelist,
+        seen_action,
+        MailAction::Blocklist,
+        MailAction::Delete,
+    ] {
+        let mut button = Button::new(action.label())
+            .icon_class(action.icon_class())
+            .class("pwt-button-text")
+            .attribute("role", "menuitem")
+            .on_activate({
+                let on_action = on_action.clone();
+                let on_close = on_close.clone();
+                move |_| {
+                    on_close.emit(());
+                    on_action.emit(action);
+                }
+            });
+        if action == MailAction::Delete {
+            button = button.class(ColorScheme::Error);
+        }
+        actions.add_child(button);
+    }
+}
+
+/// Bottom sheet with the actions for the mail shown in the preview.
+pub(crate) fn preview_action_sheet(
+    seen: bool,
+    on_action: Callback<MailAction>,
+    on_close: Callback<()>,
+) -> SideDialog {
+    let mut actions = Column::new()
+        .padding(2)
+        .gap(1)
+        .class(JustifyContent::Stretch)
+        .attribute("role", "menu")
+        .attribute("aria-label", tr!("Actions"));
+    add_mail_actions(&mut actions, seen, on_action, on_close.clone());
+
+    SideDialog::new()
+        .location(SideDialogLocation::Bottom)
+        .on_close(on_close)
+        .with_child(actions)
+}
+
+/// Dialog confirming an action for `count` mails at once, e.g. for the
+/// selection, a group or the mails matching a new list entry.
+pub(crate) fn batch_confirm_dialog(
+    action: MailAction,
+    count: usize,
+    on_confirm: Callback<()>,
+    on_cancel: Callback<()>,
+) -> Dialog {
+    Dialog::new(tr!("Confirm"))
+        .with_child(
+            Column::new()
+                .padding(2)
+                .gap(2)
+                .with_child(action.confirm_text(count))
+                .with_child(
+                    Row::new()
+                        .gap(1)
+                        .class(JustifyContent::FlexEnd)
+                        .with_child(
+                            Button::new(tr!("Cancel"))
+                                .class("pwt-button-text")
+                                .on_activate({
+                                    let on_cancel = on_cancel.clone();
+                                    move |_| on_cancel.emit(())
+                                }),
+                        )
+                        .with_child(
+                            Button::new(action.label())
+                                .class("pwt-scheme-primary")
+                                .on_activate(move |_| on_confirm.emit(())),
+                        ),
+                ),
+        )
+        .on_close(move |_| on_cancel.emit(()))
+}
+
+/// Bottom sheet with the actions applied to all mails of a campaign group.
+pub(crate) fn group_action_sheet(
+    domain: &str,
+    count: usize,
+    on_action: Callback<MailAction>,
+    on_close: Callback<()>,
+) -> SideDialog {
+    let mut actions = Column::new()
+        .padding(2)
+        .gap(1)
+        .class(JustifyContent::Stretch)
+        .attribute("role", "menu")
+        .attribute(
+            "aria-label",
+            tr!("Actions for {0} mails from {1}", count, domain),
+        )
+        .with_child(
+            Container::new()
+                .class("pwt-font-title-small pwt-text-truncate")
+                .padding_bottom(1)
+                .with_child(tr!("{0} similar mails from {1}", count, domain)),
+        );
+
+    for (action, label) in [
+        (MailAction::Blocklist, tr!("Blocklist all senders")),
+        (MailAction::Delete, tr!("Delete all")),
+    ] {
+        let mut button = Button::new(label)
+            .icon_class(action.icon_class())
+            .class("pwt-button-text")
+            .attribute("role", "menuitem")
+            .on_activate({
+                let on_action = on_action.clone();
+                let on_close = on_close.clone();
+                move |_| {
+                    on_close.emit(());
+                    on_action.emit(action);
+                }
+            });
+        if action == MailAction::Delete {
+            button = button.class(ColorScheme::Error);
+        }
+        actions.add_child(button);
+    }
+
+    SideDialog::new()
+        .location(SideDialogLocation::Bottom)
+        .on_close(on_close)
+        .with_child(actions)
+}
@@ -0,0 +1,351 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use anyhow::Error;
+use js_sys::Date;
+
+use yew::html::IntoEventCallback;
+use yew::virtual_dom::{VComp, VNode};
+
+use pwt::css::{AlignItems, FlexFit, JustifyContent, Opacity};
+use pwt::prelude::*;
+use pwt::widget::{error_message, Button, Column, Container, Row};
+
+use crate::date_format::{
+    day_key, day_start, format_date, format_month, month_layout, parse_date_time, weekday_names,
+    year_month,
+};
+use crate::spam_list::{load_mails, MailInfo};
+
+/// Month calendar for picking a range of days, each day shaded by the number
+/// of quarantined mails received on it.
+///
+/// The first tap selects a single day, the second extends the selection to
+/// the tapped day.
+#[derive(Clone, PartialEq, Properties)]
+pub struct RangeCalendar {
+    /// Initially selected range, as start of the first and last day.
+    #[prop_or_default]
+    range: Option<(i64, i64)>,
+    /// Called with the start of the first and last selected day.
+    #[prop_or_default]
+    on_change: Option<Callback<(i64, i64)>>,
+}
+
+impl RangeCalendar {
+    pub fn new() -> Self {
+        yew::props!(Self {})
+    }
+
+    pub fn range(mut self, range: Option<(i64, i64)>) -> Self {
+        self.range = range;
+        self
+    }
+
+    pub fn on_change(mut self, cb: impl IntoEventCallback<(i64, i64)>) -> Self {
+        self.on_change = cb.into_event_callback();
+        self
+    }
+}
+
+impl Default for RangeCalendar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub enum Msg {
+    PreviousMonth,
+    NextMonth,
+    LoadResult((i64, i64), Result<Vec<MailInfo>, Error>), // (year, month)
+    Pick(i64),                                            // day start
+}
+
+pub struct PmgRangeCalendar {
+    // shown month
+    year: i64,
+    month: i64,
+    // mails per day, by day key
+    counts: HashMap<String, usize>,
+    // months whose counts are known or being loaded
+    loaded: HashSet<(i64, i64)>,
+    // months whose counts are known
+    counted: HashSet<(i64, i64)>,
+    error: Option<String>,
+    range: Option<(i64, i64)>,
+    // first tapped day while a range is being picked
+    anchor: Option<i64>,
+}
+
+impl PmgRangeCalendar {
+    fn month_start(year: i64, month: i64) -> i64 {
+        parse_date_time(&format!("{year:04}-{month:02}-01"), "").unwrap_or_default()
+    }
+
+    fn load_month(&mut self, ctx: &Context<Self>, year: i64, month: i64) {
+        if !self.loaded.insert((year, month)) {
+            return;
+        }
+        let start = Self::month_start(year, month);
+        let (_, days) = month_layout(year, month);
+        // noon of the last day, then on to the next day's start
+        let end = day_start(start + (days - 1) * 86400 + 36 * 3600);
+
+        let link = ctx.link().clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let result = load_mails(Some(start as u64), Some(end as u64), None).await;
+            link.send_message(Msg::LoadResult((year, month), result));
+        });
+    }
+
+    fn show_month(&mut self, ctx: &Context<Self>, year: i64, month: i64) {
+        self.year = year;
+        self.month = month;
+        self.error = None;
+        self.load_month(ctx, year, month);
+    }
+
+    // the selection may cover months never shown
+    fn load_selection(&mut self, ctx: &Context<Self>) {
+        let Some((first, last)) = self.range else {
+            return;
+        };
+        let (mut year, mut month) = year_month(first);
+        let end = year_month(last);
+        while (year, month) <= end {
+            self.load_month(ctx, year, month);
+            (year, month) = if month == 12 {
+                (year + 1, 1)
+            } else {
+                (year, month + 1)
+            };
+        }
+    }
+
+    // mails in the selected range, and whether the counts of all its days
+    // are known
+    fn selection_total(&self) -> Option<(usize, bool)> {
+        let (first, last) = self.range?;
+        // step from noon to noon, days are not always 24 hours long
+        let mut total = 0;
+        let mut complete = true;
+        let mut day = first + 12 * 3600;
+        while day <= last + 12 * 3600 {
+            total += self.counts.get(&day_key(day)).copied().unwrap_or(0);
+            complete &= self.counted.contains(&year_month(day));
+            day += 86400;
+        }
+        Some((total, complete))
+    }
+
+    fn day_grid(&self, ctx: &Context<Self>) -> Html {
+        let (first_weekday, days) = month_layout(self.year, self.month);
+        let start = Self::month_start(self.year, self.month);
+        let today = day_start((Date::now() / 1000.0) as i64);
+
+        let mut grid = Container::new()
+            .style("display", "grid")
+            .style("grid-template-columns", "repeat(7, 1fr)")
+            .style("gap", "2px")
+            .attribute("role", "grid");
+
+        for name in weekday_names() {
+            grid.add_child(
+                Container::new()
+                    .class("pwt-font-label-small")
+                    .class(Opacity::Half)
+                    .style("text-align", "center")
+                    .attribute("aria-hidden", "true")
+                    .with_child(name),
+            );
+        }
+        for _ in 0..first_weekday {
+            grid.add_child(Container::new());
+        }
+
+        let days: Vec<i64> = (0..days)
+            .map(|offset| day_start(start + offset * 86400 + 12 * 3600))
+            .collect();
+        let max = days
+            .iter()
+            .filter_map(|day| self.counts.get(&day_key(*day)))
+            .copied()
+            .max()
+            .unwrap_or(0);
+
+        for (pos, day) in days.into_iter().enumerate() {
+            let count = self.counts.get(&day_key(day)).copied().unwrap_or(0);
+            let selected = self
+                .range
+                .is_some_and(|(first, last)| day >= first && day <= last);
+
+            let mut cell = Container::new()
+                .padding_y(1)
+                .style("text-align", "center")
+                .style("cursor", "pointer")
+                .style("border-radius", "0.25rem")
+                .attribute("role", "gridcell")
+                .attribute("tabindex", "0")
+                .attribute("aria-selected", selected.to_string())
+                .attribute("aria-label", tr!("{0}: {1} mails", format_date(day), count))
+                .onclick(ctx.link().callback(move |_| Msg::Pick(day)))
+                .onkeydown(ctx.link().batch_callback(move |event: KeyboardEvent| {
+                    matches!(event.key().as_str(), "Enter" | " ").then(|| {
+                        event.prevent_default();
+                        Msg::Pick(day)
+                    })
+                }))
+                .with_child((pos + 1).to_string());
+
+            if selected {
+                cell = cell.class("pwt-scheme-primary");
+            } else if count > 0 {
+                let share = 15 + 60 * count / max.max(1);
+                cell = cell.style(
+                    "background-color",
+                    format!("color-mix(in srgb, var(--pwt-color-primary) {share}%, transparent)"),
+                );
+            }
+            if day == today {
+                cell = cell
+                    .style("outline", "1px solid var(--pwt-color-primary)")
+                    .style("outline-offset", "-1px");
+            }
+            grid.add_child(cell);
+        }
+        grid.into()
+    }
+}
+
+impl Component for PmgRangeCalendar {
+    type Message = Msg;
+    type Properties = RangeCalendar;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let range = ctx.props().range;
+        // start with the month of the range end, usually the current one
+        let shown = range
+            .map(|(_, last)| last)
+            .unwrap_or_else(|| (Date::now() / 1000.0) as i64);
+        let (year, month) = year_month(shown);
+
+        let mut me = Self {
+            year,
+            month,
+            counts: HashMap::new(),
+            loaded: HashSet::new(),
+            counted: HashSet::new(),
+            error: None,
+            range,
+            anchor: None,
+        };
+        me.load_month(ctx, year, month);
+        me.load_selection(ctx);
+        me
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::PreviousMonth => {
+                let (year, month) = if self.month == 1 {
+                    (self.year - 1, 12)
+                } else {
+                    (self.year, self.month - 1)
+                };
+                self.show_month(ctx, year, month);
+            }
+            Msg::NextMonth => {
+                let (year, month) = if self.month == 12 {
+                    (self.year + 1, 1)
+                } else {
+                    (self.year, self.month + 1)
+                };
+                self.show_month(ctx, year, month);
+            }
+            Msg::LoadResult(month, result) => match result {
+                Ok(mails) => {
+                    for mail in mails {
+                        *self.counts.entry(day_key(mail.time)).or_default() += 1;
+                    }
+                    self.counted.insert(month);
+                }
+                Err(err) => {
+                    // allow retrying by navigating away and back
+                    self.loaded.remove(&month);
+                    self.error = Some(err.to_string());
+                }
+            },
+            Msg::Pick(day) => {
+                let range = match self.anchor.take() {
+                    Some(anchor) => (anchor.min(day), anchor.max(day)),
+                    None => {
+                        self.anchor = Some(day);
+                        (day, day)
+                    }
+                };
+                self.range = Some(range);
+                self.load_selection(ctx);
+                if let Some(on_change) = &ctx.props().on_change {
+                    on_change.emit(range);
+                }
+            }
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let header = Row::new()
+            .class(AlignItems::Center)
+            .with_child(
+                Button::new_icon("fa fa-chevron-left")
+                    .class("circle")
+                    .attribute("aria-label", tr!("Previous month"))
+                    .on_activate(ctx.link().callback(|_| Msg::PreviousMonth)),
+            )
+            .with_child(
+                Container::new()
+                    .class(FlexFit)
+                    .class("pwt-font-title-medium")
+                    .style("text-align", "center")
+                    .attribute("aria-live", "polite")
+                    .with_child(format_month(self.year, self.month)),
+            )
+            .with_child(
+                Button::new_icon("fa fa-chevron-right")
+                    .class("circle")
+                    .attribute("aria-label", tr!("Next month"))
+                    .on_activate(ctx.link().callback(|_| Msg::NextMonth)),
+            );
+
+        let hint = match (self.anchor, self.selection_total()) {
+            (Some(_), _) => tr!("Tap the last day of the range"),
+            (None, Some((total, true))) => tr!("{0} mails in the selected range", total),
+            // still loading, or a month failed to load
+            (None, Some((total, false))) => {
+                tr!("At least {0} mails in the selected range", total)
+            }
+            (None, None) => tr!("Tap the first day of the range"),
+        };
+
+        Column::new()
+            .gap(1)
+            .with_child(header)
+            .with_child(self.day_grid(ctx))
+            .with_optional_child(self.error.as_deref().map(error_message))
+            .with_child(
+                Row::new()
+                    .class(JustifyContent::Center)
+                    .class("pwt-font-label-medium")
+                    .attribute("aria-live", "polite")
+                    .with_child(hint),
+            )
+            .into()
+    }
+}
+
+impl From<RangeCalendar> for VNode {
+    fn from(val: RangeCalendar) -> Self {
+        let comp = VComp::new::<PmgRangeCalendar>(Rc::new(val), None);
+        VNode::from(comp)
+    }
+}
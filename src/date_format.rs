@@ -0,0 +1,308 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use js_sys::{Array, Date, Function, Intl, Object, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+
+use pwt::prelude::*;
+use pwt::state::Language;
+
+use crate::settings::Settings;
+
+// Dates and times are shown in the language chosen in the app and in the
+// time zone from the settings, the device time zone if none is set.
+
+type FormatOptions = &'static [(&'static str, &'static str)];
+
+thread_local! {
+    // the time zone setting, read on first use
+    static TIME_ZONE: RefCell<Option<Option<String>>> = RefCell::new(None);
+    // creating a formatter is expensive, and a list formats many dates
+    static FORMATS: RefCell<HashMap<(String, FormatOptions), Intl::DateTimeFormat>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Drops the cached formatters and time zone, needed once the time zone
+/// setting changed.
+pub(crate) fn clear_format_cache() {
+    TIME_ZONE.with(|time_zone| time_zone.borrow_mut().take());
+    FORMATS.with(|formats| formats.borrow_mut().clear());
+}
+
+fn time_zone() -> Option<String> {
+    TIME_ZONE.with(|time_zone| {
+        time_zone
+            .borrow_mut()
+            .get_or_insert_with(|| Settings::load().time_zone)
+            .clone()
+    })
+}
+
+// Formatters are kept per locale and options, the cache only ever holds
+// those of the current time zone.
+fn date_time_format(locale: &str, options: FormatOptions) -> Intl::DateTimeFormat {
+    let key = (locale.to_string(), options);
+    if let Some(format) = FORMATS.with(|formats| formats.borrow().get(&key).cloned()) {
+        return format;
+    }
+
+    let locales = Array::new();
+    if !locale.is_empty() {
+        locales.push(&JsValue::from_str(locale));
+    }
+    let opts = Object::new();
+    for (key, value) in options {
+        let _ = Reflect::set(&opts, &JsValue::from_str(key), &JsValue::from_str(value));
+    }
+    if let Some(time_zone) = time_zone() {
+        let _ = Reflect::set(
+            &opts,
+            &JsValue::from_str("timeZone"),
+            &JsValue::from_str(&time_zone),
+        );
+    }
+    let format = Intl::DateTimeFormat::new(&locales, &opts);
+    FORMATS.with(|formats| formats.borrow_mut().insert(key, format.clone()));
+    format
+}
+
+fn format_with(epoch: i64, options: FormatOptions) -> String {
+    let date = Date::new(&JsValue::from_f64(1000.0 * epoch as f64));
+    let format = date_time_format(&Language::load(), options);
+    format
+        .format()
+        .call1(&format, &date)
+        .ok()
+        .and_then(|text| text.as_string())
+        .unwrap_or_default()
+}
+
+/// Date in the user's language, e.g. "Mar 5, 2024".
+pub(crate) fn format_date(epoch: i64) -> String {
+    format_with(epoch, &[("dateStyle", "medium")])
+}
+
+/// Time of day in the user's language, e.g. "14:05" or "2:05 PM".
+pub(crate) fn format_time(epoch: i64) -> String {
+    format_with(epoch, &[("timeStyle", "short")])
+}
+
+// (year, month, day, hour, minute, second) of the wall clock in the
+// configured time zone
+fn wall_clock(epoch: i64) -> [i64; 6] {
+    let date = Date::new(&JsValue::from_f64(1000.0 * epoch as f64));
+    // this locale formats as "2024-03-05, 14:05:09", which is easy to split
+    let format = date_time_format(
+        "en-CA",
+        &[
+            ("year", "numeric"),
+            ("month", "2-digit"),
+            ("day", "2-digit"),
+            ("hour", "2-digit"),
+            ("minute", "2-digit"),
+            ("second", "2-digit"),
+            ("hourCycle", "h23"),
+        ],
+    );
+    let text = format
+        .format()
+        .call1(&format, &date)
+        .ok()
+        .and_then(|text| text.as_string())
+        .unwrap_or_default();
+
+    let mut parts = [0; 6];
+    for (part, value) in parts.iter_mut().zip(
+        text.split(|c: char| !c.is_ascii_digit())
+            .filter(|s| !s.is_empty()),
+    ) {
+        *part = value.parse().unwrap_or(0);
+    }
+    parts
+}
+
+/// Identifies the day containing `epoch` as `YYYY-MM-DD`, which is also the
+/// format of date input fields.
+pub(crate) fn day_key(epoch: i64) -> String {
+    let [year, month, day, ..] = wall_clock(epoch);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Time of day of `epoch` as `HH:MM`, the format of time input fields.
+pub(crate) fn time_key(epoch: i64) -> String {
+    let [.., hour, minute, _] = wall_clock(epoch);
+    format!("{hour:02}:{minute:02}")
+}
+
+// days since 1970-01-01 of a proleptic Gregorian date
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+// The epoch at which the wall clock in the configured time zone shows
+// `wanted`, given as seconds since 1970-01-01 00:00 of that wall clock.
+fn from_wall_clock(wanted: i64) -> i64 {
+    // start with the wall clock time read as UTC and correct it by the offset
+    // of the time zone, twice to settle on the right side of a DST switch
+    let mut epoch = wanted;
+    for _ in 0..2 {
+        let [y, mo, d, h, mi, sec] = wall_clock(epoch);
+        let shown = days_from_civil(y, mo, d) * 86400 + h * 3600 + mi * 60 + sec;
+        epoch += wanted - shown;
+    }
+    epoch
+}
+
+// The values of a date and an optional time input field as seconds since
+// 1970-01-01 00:00 of the wall clock, see [`from_wall_clock`].
+fn parse_wall_clock(date: &str, time: &str) -> Option<i64> {
+    let mut parts = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (parts.next()??, parts.next()??, parts.next()??);
+    let (hour, minute) = match time.split_once(':') {
+        Some((hour, minute)) => (hour.parse::<i64>().ok()?, minute.parse::<i64>().ok()?),
+        None if time.is_empty() => (0, 0),
+        None => return None,
+    };
+    // also rejects dates like February 30th, which the arithmetic below rolls over
+    if !(1..=12).contains(&month)
+        || !(1..=month_layout(year, month).1).contains(&day)
+        || !(0..=23).contains(&hour)
+        || !(0..=59).contains(&minute)
+    {
+        return None;
+    }
+    Some(days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60)
+}
+
+/// Parses the values of a date (`YYYY-MM-DD`) and an optional time (`HH:MM`)
+/// input field into an epoch, interpreted in the configured time zone.
+pub(crate) fn parse_date_time(date: &str, time: &str) -> Option<i64> {
+    parse_wall_clock(date, time).map(from_wall_clock)
+}
+
+/// Year and month (1-12) of the day containing `epoch`.
+pub(crate) fn year_month(epoch: i64) -> (i64, i64) {
+    let [year, month, ..] = wall_clock(epoch);
+    (year, month)
+}
+
+/// Weekday of the first day (0 for Monday) and number of days of a month.
+pub(crate) fn month_layout(year: i64, month: i64) -> (i64, i64) {
+    let first = days_from_civil(year, month, 1);
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    // 1970-01-01 was a Thursday
+    let weekday = (first + 3).rem_euclid(7);
+    (weekday, days_from_civil(next_year, next_month, 1) - first)
+}
+
+/// Name of a month in the user's language, e.g. "March 2024".
+pub(crate) fn format_month(year: i64, month: i64) -> String {
+    // the middle of the month is safe from any time zone shift
+    let epoch = days_from_civil(year, month, 15) * 86400;
+    format_with(epoch, &[("year", "numeric"), ("month", "long")])
+}
+
+/// Short weekday names in the user's language, starting with Monday.
+pub(crate) fn weekday_names() -> Vec<String> {
+    // 2024-01-01 was a Monday
+    (1..=7)
+        .map(|day| {
+            let epoch = parse_date_time(&format!("2024-01-{day:02}"), "12:00").unwrap_or_default();
+            format_with(epoch, &[("weekday", "short")])
+        })
+        .collect()
+}
+
+// days since 1970-01-01 of the date of `epoch` in the configured time zone
+fn day_number(epoch: i64) -> i64 {
+    let [year, month, day, ..] = wall_clock(epoch);
+    days_from_civil(year, month, day)
+}
+
+/// Start of the day containing `epoch`, from the date rather than the time of
+/// day, which is off by the shift on days with a DST switch.
+pub(crate) fn day_start(epoch: i64) -> i64 {
+    from_wall_clock(day_number(epoch) * 86400)
+}
+
+/// Start of the day after the one containing `epoch`, which is not always 24
+/// hours later.
+pub(crate) fn next_day_start(epoch: i64) -> i64 {
+    from_wall_clock((day_number(epoch) + 1) * 86400)
+}
+
+/// Label for a date header: "Today", "Yesterday", the weekday for the rest of
+/// the last week, the date otherwise.
+pub(crate) fn day_label(epoch: i64) -> String {
+    let days_ago = day_number((Date::now() / 1000.0) as i64) - day_number(epoch);
+    match days_ago {
+        0 => tr!("Today"),
+        1 => tr!("Yesterday"),
+        2..=6 => format_with(epoch, &[("weekday", "long")]),
+        _ => format_date(epoch),
+    }
+}
+
+/// Time zones the browser knows about, for choosing a fixed one.
+pub(crate) fn time_zones() -> Vec<String> {
+    // Intl.supportedValuesOf is not available in older browsers
+    let Ok(intl) = Reflect::get(&js_sys::global(), &JsValue::from_str("Intl")) else {
+        return Vec::new();
+    };
+    let Ok(func) = Reflect::get(&intl, &JsValue::from_str("supportedValuesOf")) else {
+        return Vec::new();
+    };
+    let Some(func) = func.dyn_ref::<Function>() else {
+        return Vec::new();
+    };
+    func.call1(&intl, &JsValue::from_str("timeZone"))
+        .ok()
+        .and_then(|zones| zones.dyn_into::<Array>().ok())
+        .map(|zones| zones.iter().filter_map(|zone| zone.as_string()).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wall_clock_of_input_values() {
+        assert_eq!(parse_wall_clock("1970-01-01", ""), Some(0));
+        assert_eq!(parse_wall_clock("1970-01-02", "01:30"), Some(86400 + 5400));
+        assert_eq!(parse_wall_clock("2024-03-05", "14:05"), Some(1709647500));
+        assert_eq!(parse_wall_clock("2024-02-29", "23:59"), Some(1709251140));
+        assert_eq!(parse_wall_clock("1969-12-31", ""), Some(-86400));
+    }
+
+    // invalid values are rejected before the time zone is looked at, so
+    // these need no browser
+    #[test]
+    fn parse_date_time_rejects_invalid_values() {
+        for (date, time) in [
+            ("", ""),
+            ("2024-03", ""),
+            ("2024-03-xx", ""),
+            ("2024-13-01", ""),
+            ("2024-00-01", ""),
+            ("2024-04-31", ""),
+            ("2023-02-29", ""),
+            ("2024-02-30", ""),
+            ("2024-03-05", "24:00"),
+            ("2024-03-05", "12:60"),
+            ("2024-03-05", "1205"),
+            ("2024-03-05", "12:"),
+        ] {
+            assert_eq!(parse_date_time(date, time), None, "{date} {time}");
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use gloo_utils::{history, window};
+use js_sys::{Object, Reflect};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::Event;
+
+use pwt::prelude::*;
+
+// key of the overlay depth in the state of the history entries we push
+const DEPTH_KEY: &str = "pmgOverlayDepth";
+
+// number of overlay entries up to and including the current history entry
+fn current_depth() -> u32 {
+    history()
+        .state()
+        .ok()
+        .and_then(|state| Reflect::get(&state, &JsValue::from_str(DEPTH_KEY)).ok())
+        .and_then(|depth| depth.as_f64())
+        .unwrap_or(0.0) as u32
+}
+
+/// Adds a history entry while a dialog, sheet or menu is open, so the back
+/// button (e.g. the one of Android) closes it instead of leaving the page.
+///
+/// Dropping the guard, because the overlay was closed by other means, removes
+/// the entry again.
+pub(crate) struct HistoryGuard {
+    depth: u32,
+    popped: Rc<Cell<bool>>,
+    listener: Closure<dyn Fn(Event)>,
+}
+
+impl HistoryGuard {
+    pub fn new(on_back: Callback<()>) -> Self {
+        let depth = current_depth() + 1;
+        let state = Object::new();
+        let _ = Reflect::set(&state, &JsValue::from_str(DEPTH_KEY), &JsValue::from(depth));
+        if let Err(err) = history().push_state(&state, "") {
+            log::error!("could not add history entry: {err:?}");
+        }
+
+        let popped = Rc::new(Cell::new(false));
+        let listener = Closure::<dyn Fn(Event)>::new({
+            let popped = popped.clone();
+            move |_| {
+                // the user may go back several entries at once
+                if !popped.get() && current_depth() < depth {
+                    popped.set(true);
+                    on_back.emit(());
+                }
+            }
+        });
+        if let Err(err) =
+            window().add_event_listener_with_callback("popstate", listener.as_ref().unchecked_ref())
+        {
+            log::error!("could not register history listener: {err:?}");
+        }
+
+        Self {
+            depth,
+            popped,
+            listener,
+        }
+    }
+
+    /// Whether the entry was already removed by going back.
+    pub fn popped(&self) -> bool {
+        self.popped.get()
+    }
+
+    /// Leaves the entry in place when dropped, for a navigation that
+    /// replaces it.
+    pub fn release(self) {
+        self.popped.set(true);
+    }
+}
+
+impl Drop for HistoryGuard {
+    fn drop(&mut self) {
+        let _ = window().remove_event_listener_with_callback(
+            "popstate",
+            self.listener.as_ref().unchecked_ref(),
+        );
+        if self.popped.get() {
+            return;
+        }
+        // deferred, as the overlay may close for a navigation that pushes its
+        // own entry right afterwards, which must not be undone
+        let depth = self.depth;
+        wasm_bindgen_futures::spawn_local(async move {
+            if current_depth() == depth {
+                let _ = history().back();
+            }
+        });
+    }
+}
+
+/// Creates or drops `guard` to match whether an overlay is `open`.
+///
+/// Components call this from `rendered`, with `on_back` closing their
+/// topmost overlay. A new entry is added if going back left one open.
+pub(crate) fn sync_history_guard(
+    guard: &mut Option<HistoryGuard>,
+    open: bool,
+    on_back: impl FnOnce() -> Callback<()>,
+) {
+    if !open {
+        *guard = None;
+    } else if guard.as_ref().map_or(true, HistoryGuard::popped) {
+        *guard = Some(HistoryGuard::new(on_back()));
+    }
+}
@@ -0,0 +1,138 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use gloo_utils::window;
+use js_sys::Date;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::Event;
+use yew::platform::time::sleep;
+
+use pwt::prelude::*;
+
+use crate::settings::{load_item, store_item, Settings};
+
+// how long before the logout the countdown is shown
+const WARNING_PERIOD: i64 = 60;
+
+// how often the timeout is checked while no countdown is shown, so changes of
+// the setting apply soon enough
+const CHECK_INTERVAL: i64 = 10;
+
+// user input that counts as activity
+const ACTIVITY_EVENTS: [&str; 4] = ["pointerdown", "keydown", "wheel", "touchstart"];
+
+// The last activity in any tab of the app, so a tab in the background does
+// not log out the one in use.
+const ACTIVITY_KEY: &str = "pmg-quarantine-activity";
+
+// how often activity is written to the local storage at most, in seconds
+const ACTIVITY_STORE_INTERVAL: i64 = 5;
+
+/// Choices for the idle timeout in minutes, 0 turns it off.
+pub(crate) const IDLE_TIMEOUT_CHOICES: [u32; 5] = [0, 5, 15, 30, 60];
+
+fn now() -> i64 {
+    (Date::now() / 1000.0) as i64
+}
+
+/// Idle timeout in minutes, 0 means no timeout. A timeout set by the server
+/// takes precedence, the user's setting only applies without one.
+pub(crate) fn idle_timeout(server_default: u32) -> u32 {
+    if server_default > 0 {
+        return server_default;
+    }
+    Settings::load().idle_timeout.unwrap_or(0)
+}
+
+fn shared_activity() -> Option<i64> {
+    load_item(ACTIVITY_KEY)?.parse().ok()
+}
+
+/// Logs out after a period without user input in any tab of the app, for
+/// devices shared by several people.
+///
+/// `on_warning` gets the seconds left once the logout is near, and `None`
+/// when the user became active again, `on_timeout` is called once the time
+/// is up. Watching stops once this is dropped, i.e. on logout.
+pub(crate) struct IdleWatcher {
+    alive: Rc<Cell<bool>>,
+    listener: Closure<dyn Fn(Event)>,
+}
+
+impl IdleWatcher {
+    pub fn start(
+        server_default: u32,
+        on_warning: Callback<Option<i64>>,
+        on_timeout: Callback<()>,
+    ) -> Self {
+        // a new session, e.g. after a login, counts as activity
+        let last_activity = Rc::new(Cell::new(now()));
+        store_item(ACTIVITY_KEY, &last_activity.get().to_string());
+        let listener = Closure::<dyn Fn(Event)>::new({
+            let last_activity = last_activity.clone();
+            let last_stored = Cell::new(last_activity.get());
+            move |_| {
+                let now = now();
+                last_activity.set(now);
+                if now - last_stored.get() >= ACTIVITY_STORE_INTERVAL {
+                    last_stored.set(now);
+                    store_item(ACTIVITY_KEY, &now.to_string());
+                }
+            }
+        });
+        for event in ACTIVITY_EVENTS {
+            if let Err(err) =
+                window().add_event_listener_with_callback(event, listener.as_ref().unchecked_ref())
+            {
+                log::error!("could not watch '{event}' events: {err:?}");
+            }
+        }
+
+        let alive = Rc::new(Cell::new(true));
+        let running = alive.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let mut warned = false;
+            while running.get() {
+                // re-read every round so changing the setting needs no restart
+                let timeout = idle_timeout(server_default) as i64 * 60;
+                let last = shared_activity().map_or(last_activity.get(), |shared| {
+                    shared.max(last_activity.get())
+                });
+                let remaining = timeout - (now() - last);
+                let wait = if timeout == 0 || remaining > WARNING_PERIOD {
+                    if warned {
+                        warned = false;
+                        on_warning.emit(None);
+                    }
+                    if timeout == 0 {
+                        CHECK_INTERVAL
+                    } else {
+                        (remaining - WARNING_PERIOD).min(CHECK_INTERVAL)
+                    }
+                } else if remaining > 0 {
+                    warned = true;
+                    on_warning.emit(Some(remaining));
+                    1
+                } else {
+                    on_timeout.emit(());
+                    return;
+                };
+                sleep(Duration::from_secs(wait as u64)).await;
+            }
+        });
+
+        Self { alive, listener }
+    }
+}
+
+impl Drop for IdleWatcher {
+    fn drop(&mut self) {
+        self.alive.set(false);
+        for event in ACTIVITY_EVENTS {
+            let _ = window()
+                .remove_event_listener_with_callback(event, self.listener.as_ref().unchecked_ref());
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use gloo_utils::document;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{Element, KeyboardEvent};
+
+use pwt::css::{AlignItems, FlexFit};
+use pwt::prelude::*;
+use pwt::widget::{Column, Container, Dialog, Row};
+
+use crate::MailAction;
+
+/// Keyboard shortcuts for triaging mail in the list and the mail view.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Shortcut {
+    Next,
+    Previous,
+    Open,
+    Back,
+    Action(MailAction),
+    ToggleSeen,
+    Help,
+}
+
+impl Shortcut {
+    /// Maps a key press to a shortcut, ignoring key presses that belong to
+    /// text input or carry modifiers used by browser or system shortcuts.
+    pub fn from_event(event: &KeyboardEvent) -> Option<Self> {
+        if event.ctrl_key() || event.alt_key() || event.meta_key() || is_text_input(event) {
+            return None;
+        }
+        Some(match event.key().as_str() {
+            "j" | "ArrowDown" => Shortcut::Next,
+            "k" | "ArrowUp" => Shortcut::Previous,
+            "Enter" => Shortcut::Open,
+            "Escape" => Shortcut::Back,
+            "d" => Shortcut::Action(MailAction::Deliver),
+            "x" => Shortcut::Action(MailAction::Delete),
+            "w" => Shortcut::Action(MailAction::Welcomelist),
+            "b" => Shortcut::Action(MailAction::Blocklist),
+            "u" => Shortcut::ToggleSeen,
+            "?" => Shortcut::Help,
+            _ => return None,
+        })
+    }
+}
+
+fn is_text_input(event: &KeyboardEvent) -> bool {
+    let Some(element) = event.target().and_then(|t| t.dyn_into::<Element>().ok()) else {
+        return false;
+    };
+    // also covers the pickers of widgets, which are no form fields themselves
+    element
+        .closest(
+            "input, textarea, select, [contenteditable], \
+             [role=combobox], [role=listbox], [role=textbox]",
+        )
+        .ok()
+        .flatten()
+        .is_some()
+}
+
+/// Forwards all `keydown` events of the document while alive.
+///
+/// Components decode them with [`Shortcut::from_event`] in their update
+/// function, so they can decide based on their state whether to handle a
+/// shortcut and only then prevent the browser default.
+pub(crate) struct KeyListener {
+    listener: Closure<dyn Fn(KeyboardEvent)>,
+}
+
+impl KeyListener {
+    pub fn new(callback: Callback<KeyboardEvent>) -> Self {
+        let listener = Closure::<dyn Fn(KeyboardEvent)>::new(move |event| callback.emit(event));
+        if let Err(err) = document()
+            .add_event_listener_with_callback("keydown", listener.as_ref().unchecked_ref())
+        {
+            log::error!("could not register keyboard listener: {err:?}");
+        }
+        Self { listener }
+    }
+}
+
+impl Drop for KeyListener {
+    fn drop(&mut self) {
+        let _ = document()
+            .remove_event_listener_with_callback("keydown", self.listener.as_ref().unchecked_ref());
+    }
+}
+
+/// Overlay listing all keyboard shortcuts.
+pub(crate) fn shortcut_help(on_close: Callback<()>) -> Dialog {
+    let shortcuts = [
+        ("j / ↓", tr!("Next mail")),
+        ("k / ↑", tr!("Previous mail")),
+        ("Enter", tr!("Open mail, expand group")),
+        ("Esc", tr!("Go back")),
+        ("d", tr!("Deliver")),
+        ("x", tr!("Delete")),
+        ("w", tr!("Welcomelist")),
+        ("b", tr!("Blocklist")),
+        ("u", tr!("Toggle seen")),
+        ("?", tr!("Show this help")),
+    ];
+
+    let mut list = Column::new().padding(2).gap(1);
+    for (key, description) in shortcuts {
+        list.add_child(
+            Row::new()
+                .gap(2)
+                .class(AlignItems::Center)
+                .with_child(
+                    Container::new()
+                        .class("pwt-font-label-large")
+                        .min_width("4rem")
+                        .with_child(html! { <kbd>{key}</kbd> }),
+                )
+                .with_child(Container::new().class(FlexFit).with_child(description)),
+        );
+    }
+
+    Dialog::new(tr!("Keyboard Shortcuts"))
+        .with_child(list)
+        .on_close(on_close)
+}
@@ -0,0 +1,292 @@
+use std::rc::Rc;
+
+use anyhow::{format_err, Error};
+use gloo_utils::window;
+use js_sys::{Function, Promise, Reflect};
+use percent_encoding::percent_decode_str;
+use url::{Position, Url};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use yew::html::IntoEventCallback;
+use yew::virtual_dom::{VComp, VNode};
+
+use pwt::css::{AlignItems, JustifyContent, Opacity};
+use pwt::prelude::*;
+use pwt::touch::{SideDialog, SideDialogLocation, SnackBar, SnackBarContextExt};
+use pwt::widget::{Button, Column, Container, Fa, Row};
+
+use crate::a11y::announce;
+use crate::mail_content::MailLink;
+
+// second level labels under which country code top level domains hand out
+// domains, e.g. example.co.uk
+const SECOND_LEVEL_LABELS: [&str; 12] = [
+    "ac", "co", "com", "edu", "gov", "go", "gv", "ltd", "ne", "net", "or", "org",
+];
+
+/// The part of `host` one can register, e.g. `example.co.uk` for
+/// `www.example.co.uk`. Only an approximation without the public suffix list,
+/// but good enough to point out where a link really leads.
+pub(crate) fn registrable_domain(host: &str) -> &str {
+    // IP addresses are shown in full
+    if host.starts_with('[') || host.parse::<std::net::Ipv4Addr>().is_ok() {
+        return host;
+    }
+    let labels: Vec<&str> = host.rsplitn(4, '.').collect();
+    let count = match labels.as_slice() {
+        [tld, second, _, ..] if tld.len() == 2 && SECOND_LEVEL_LABELS.contains(second) => 3,
+        _ => 2,
+    };
+    if labels.len() <= count {
+        return host;
+    }
+    let suffix_len: usize = labels[..count].iter().map(|label| label.len() + 1).sum();
+    &host[host.len() + 1 - suffix_len..]
+}
+
+// The host the visible text of a link names, if it looks like an address,
+// e.g. "www.bank.example/login" or "https://bank.example".
+fn text_host(text: &str) -> Option<String> {
+    let token = text
+        .split_whitespace()
+        .next()?
+        .trim_matches(|c: char| matches!(c, '<' | '>' | '(' | ')' | '"' | '\'' | ',' | '.'));
+    let url = if token.contains("://") {
+        Url::parse(token).ok()?
+    } else {
+        // a bare domain, but no mail address or plain words
+        if token.contains('@') || !token.contains('.') {
+            return None;
+        }
+        Url::parse(&format!("http://{token}")).ok()?
+    };
+    let host = url.host_str()?.to_ascii_lowercase();
+    // needs a top level domain of letters
+    let tld = host.rsplit('.').next()?;
+    (tld.len() >= 2 && tld.chars().all(|c| c.is_ascii_alphabetic())).then_some(host)
+}
+
+async fn copy_to_clipboard(text: &str) -> Result<(), Error> {
+    let js_err = |err: JsValue| format_err!("{err:?}");
+    let clipboard =
+        Reflect::get(&window().navigator(), &JsValue::from_str("clipboard")).map_err(js_err)?;
+    if clipboard.is_undefined() {
+        return Err(format_err!("clipboard not available"));
+    }
+    let write: Function = Reflect::get(&clipboard, &JsValue::from_str("writeText"))
+        .map_err(js_err)?
+        .dyn_into()
+        .map_err(js_err)?;
+    let promise: Promise = write
+        .call1(&clipboard, &JsValue::from_str(text))
+        .map_err(js_err)?
+        .dyn_into()
+        .map_err(js_err)?;
+    JsFuture::from(promise).await.map_err(js_err)?;
+    Ok(())
+}
+
+/// Bottom sheet for a link activated in a quarantined mail, showing where it
+/// really leads before opening it.
+#[derive(Clone, PartialEq, Properties)]
+pub struct LinkSheet {
+    link: MailLink,
+    #[prop_or_default]
+    on_close: Option<Callback<()>>,
+}
+
+impl LinkSheet {
+    pub fn new(link: MailLink) -> Self {
+        yew::props!(Self { link })
+    }
+
+    pub fn on_close(mut self, cb: impl IntoEventCallback<()>) -> Self {
+        self.on_close = cb.into_event_callback();
+        self
+    }
+}
+
+pub enum Msg {
+    Open,
+    Copy,
+    CopyResult(Result<(), Error>),
+    Close,
+}
+
+pub struct PmgLinkSheet {}
+
+impl PmgLinkSheet {
+    fn close(ctx: &Context<Self>) {
+        if let Some(on_close) = &ctx.props().on_close {
+            on_close.emit(());
+        }
+    }
+
+    // the target with the registrable domain highlighted, decoded so
+    // encoded characters cannot hide parts of it
+    fn target_view(url: Option<&Url>, href: &str) -> Html {
+        let decode = |text: &str| percent_decode_str(text).decode_utf8_lossy().to_string();
+        let mut target = Container::new()
+            .class("pwt-font-body-large")
+            .style("overflow-wrap", "anywhere")
+            .style("font-family", "monospace");
+
+        match url.and_then(|url| url.host_str().map(|host| (url, host))) {
+            Some((url, host)) => {
+                let domain = registrable_domain(host);
+                let subdomain = &host[..host.len() - domain.len()];
+                target.add_child(format!("{}://{subdomain}", url.scheme()));
+                target.add_child(html! {
+                    <strong style="color: var(--pwt-color-primary)">{domain}</strong>
+                });
+                target.add_child(decode(&url[Position::BeforePort..]));
+            }
+            None => target.add_child(decode(href)),
+        }
+        target.into()
+    }
+}
+
+impl Component for PmgLinkSheet {
+    type Message = Msg;
+    type Properties = LinkSheet;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {}
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let props = ctx.props();
+        match msg {
+            Msg::Open => {
+                // without access to the app or the mail
+                if let Err(err) = window().open_with_url_and_target_and_features(
+                    &props.link.href,
+                    "_blank",
+                    "noopener,noreferrer",
+                ) {
+                    log::error!("could not open link: {err:?}");
+                }
+                Self::close(ctx);
+            }
+            Msg::Copy => {
+                let link = ctx.link().clone();
+                let href = props.link.href.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    link.send_message(Msg::CopyResult(copy_to_clipboard(&href).await));
+                });
+            }
+            Msg::CopyResult(result) => {
+                let message = match result {
+                    Ok(()) => tr!("Link copied"),
+                    Err(err) => tr!("Could not copy link: {0}", err),
+                };
+                announce(&message);
+                ctx.link().show_snackbar(SnackBar::new().message(message));
+                Self::close(ctx);
+            }
+            Msg::Close => Self::close(ctx),
+        }
+        false
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+        let url = Url::parse(&props.link.href).ok();
+        let scheme = url.as_ref().map(|url| url.scheme());
+        let openable = matches!(scheme, Some("http" | "https" | "mailto"));
+
+        let mut warnings = Vec::new();
+        let host = url.as_ref().and_then(|url| url.host_str());
+        if let (Some(host), Some(shown)) = (host, text_host(&props.link.text)) {
+            let domain = registrable_domain(host);
+            if !registrable_domain(&shown).eq_ignore_ascii_case(domain) {
+                warnings.push(tr!(
+                    "The mail shows this link as {0}, but it leads to {1}",
+                    shown,
+                    domain
+                ));
+            }
+        }
+        if url.as_ref().is_some_and(|url| !url.username().is_empty()) {
+            warnings.push(tr!(
+                "The link contains a user name, which can disguise its real target"
+            ));
+        }
+        if !openable {
+            warnings.push(tr!("Links of this kind cannot be opened from a mail"));
+        }
+
+        let mut content = Column::new()
+            .padding(2)
+            .gap(2)
+            .class(JustifyContent::Stretch)
+            .with_child(
+                Container::new()
+                    .class("pwt-font-title-medium")
+                    .with_child(tr!("Open Link?")),
+            )
+            .with_child(
+                Container::new()
+                    .class("pwt-font-label-medium")
+                    .class(Opacity::Half)
+                    .with_child(tr!("This link is part of a quarantined mail and leads to:")),
+            )
+            .with_child(Self::target_view(url.as_ref(), &props.link.href));
+
+        for warning in warnings {
+            content.add_child(
+                Row::new()
+                    .gap(1)
+                    .class(AlignItems::Center)
+                    .with_child(
+                        Container::new()
+                            .style("color", "var(--pwt-color-error)")
+                            .with_child(Fa::new("exclamation-triangle")),
+                    )
+                    .with_child(warning),
+            );
+        }
+
+        let mut actions = Column::new().gap(1).class(JustifyContent::Stretch);
+        if openable {
+            actions.add_child(
+                Button::new(tr!("Open in New Tab"))
+                    .icon_class("fa fa-external-link")
+                    .class("pwt-button-outline")
+                    .on_activate(ctx.link().callback(|_| Msg::Open)),
+            );
+        }
+        actions.add_child(
+            Button::new(tr!("Copy Link"))
+                .icon_class("fa fa-clipboard")
+                .class("pwt-button-outline")
+                .on_activate(ctx.link().callback(|_| Msg::Copy)),
+        );
+        actions.add_child(
+            Button::new(tr!("Cancel"))
+                .class("pwt-scheme-primary")
+                .on_activate(ctx.link().callback(|_| Msg::Close)),
+        );
+        content.add_child(actions);
+
+        let on_close = props.on_close.clone();
+        SideDialog::new()
+            .location(SideDialogLocation::Bottom)
+            .on_close(move |_| {
+                if let Some(on_close) = &on_close {
+                    on_close.emit(());
+                }
+            })
+            .with_child(content)
+            .into()
+    }
+}
+
+impl From<LinkSheet> for VNode {
+    fn from(val: LinkSheet) -> Self {
+        let comp = VComp::new::<PmgLinkSheet>(Rc::new(val), None);
+        VNode::from(comp)
+    }
+}
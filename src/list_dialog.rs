@@ -0,0 +1,307 @@
+use std::rc::Rc;
+
+use anyhow::Error;
+
+use yew::html::IntoEventCallback;
+use yew::virtual_dom::{VComp, VNode};
+
+use pwt::css::{AlignItems, JustifyContent, Opacity};
+use pwt::prelude::*;
+use pwt::touch::SnackBar;
+use pwt::widget::form::{Checkbox, Field, Form, FormContext};
+use pwt::widget::{
+    error_message, get_unique_element_id, Button, Column, Container, Dialog, FieldLabel, Row,
+};
+
+use crate::sender_lists::{add_to_sender_list, domain_pattern, valid_entry, SenderList};
+use crate::spam_list::sender_address;
+use crate::MailAction;
+
+/// Result of a [`ListDialog`], handed to `on_done` once the entry was created.
+#[derive(Clone, PartialEq)]
+pub struct AddedEntry {
+    pub list: SenderList,
+    pub entry: String,
+    /// Action the user chose to run on the mail itself as well.
+    pub followup: Option<MailAction>,
+}
+
+/// Snackbar reporting a new list entry. If other mails match the entry, it
+/// offers to deliver or delete them too, handing ids and action to
+/// `on_apply`.
+pub(crate) fn retroactive_snackbar(
+    message: String,
+    list: SenderList,
+    others: Vec<String>,
+    on_apply: Callback<(Vec<String>, MailAction)>,
+) -> SnackBar {
+    let snackbar = SnackBar::new().message(message);
+    if others.is_empty() {
+        return snackbar;
+    }
+    let action = list.mail_action();
+    let label = match action {
+        MailAction::Deliver => tr!("Also deliver {0} other mails", others.len()),
+        _ => tr!("Also delete {0} other mails", others.len()),
+    };
+    snackbar
+        .action_label(label)
+        .on_action(move |_| on_apply.emit((others.clone(), action)))
+}
+
+/// Dialog to put the sender of a mail on the welcomelist or blocklist, as
+/// exact address, whole domain or an edited pattern.
+#[derive(Clone, PartialEq, Properties)]
+pub struct ListDialog {
+    list: SenderList,
+    /// The `From` header of the mail.
+    from: String,
+    #[prop_or_default]
+    on_close: Option<Callback<()>>,
+    #[prop_or_default]
+    on_done: Option<Callback<AddedEntry>>,
+}
+
+impl ListDialog {
+    pub fn new(list: SenderList, from: impl Into<String>) -> Self {
+        yew::props!(Self {
+            list,
+            from: from.into()
+        })
+    }
+
+    pub fn on_close(mut self, cb: impl IntoEventCallback<()>) -> Self {
+        self.on_close = cb.into_event_callback();
+        self
+    }
+
+    pub fn on_done(mut self, cb: impl IntoEventCallback<AddedEntry>) -> Self {
+        self.on_done = cb.into_event_callback();
+        self
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Target {
+    Address,
+    Domain,
+    Custom,
+}
+
+pub enum Msg {
+    Target(Target),
+    Followup(bool), // on/off
+    Submit,
+    Result(String, Result<(), Error>), // entry
+}
+
+pub struct PmgListDialog {
+    target: Target,
+    followup: bool,
+    form_context: FormContext,
+    submitting: bool,
+    error: Option<String>,
+}
+
+impl PmgListDialog {
+    fn entry(&self, ctx: &Context<Self>) -> String {
+        let address = sender_address(&ctx.props().from);
+        match self.target {
+            Target::Address => address,
+            Target::Domain => domain_pattern(&address),
+            Target::Custom => self
+                .form_context
+                .read()
+                .get_field_value("pattern")
+                .and_then(|value| value.as_str().map(|s| s.trim().to_lowercase()))
+                .unwrap_or_default(),
+        }
+    }
+
+    fn target_button(&self, ctx: &Context<Self>, target: Target, label: String) -> Button {
+        let checked = self.target == target;
+        Button::new(label)
+            .icon_class(if checked {
+                "fa fa-dot-circle-o"
+            } else {
+                "fa fa-circle-o"
+            })
+            .class("pwt-button-text")
+            .attribute("role", "radio")
+            .attribute("aria-checked", checked.to_string())
+            .on_activate(ctx.link().callback(move |_| Msg::Target(target)))
+    }
+}
+
+impl Component for PmgListDialog {
+    type Message = Msg;
+    type Properties = ListDialog;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            target: Target::Address,
+            followup: true,
+            form_context: FormContext::new(),
+            submitting: false,
+            error: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let props = ctx.props();
+        match msg {
+            Msg::Target(target) => {
+                self.target = target;
+                self.error = None;
+            }
+            Msg::Followup(followup) => self.followup = followup,
+            Msg::Submit => {
+                let entry = self.entry(ctx);
+                if !valid_entry(&entry) {
+                    self.error = Some(tr!("'{0}' is not a valid address or pattern", entry));
+                    return true;
+                }
+                self.submitting = true;
+                self.error = None;
+                let link = ctx.link().clone();
+                let list = props.list;
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = add_to_sender_list(list, &entry).await;
+                    link.send_message(Msg::Result(entry, result));
+                });
+            }
+            Msg::Result(entry, result) => {
+                self.submitting = false;
+                match result {
+                    Ok(()) => {
+                        if let Some(on_done) = &props.on_done {
+                            on_done.emit(AddedEntry {
+                                list: props.list,
+                                entry,
+                                followup: self.followup.then_some(props.list.mail_action()),
+                            });
+                        }
+                        if let Some(on_close) = &props.on_close {
+                            on_close.emit(());
+                        }
+                    }
+                    Err(err) => self.error = Some(err.to_string()),
+                }
+            }
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+        let address = sender_address(&props.from);
+        let title = match props.list {
+            SenderList::Welcomelist => tr!("Welcomelist Sender"),
+            SenderList::Blocklist => tr!("Blocklist Sender"),
+        };
+
+        let targets = Column::new()
+            .class(JustifyContent::Stretch)
+            .attribute("role", "radiogroup")
+            .attribute("aria-label", tr!("Entry"))
+            .with_child(self.target_button(ctx, Target::Address, tr!("Address {0}", address)))
+            .with_child(self.target_button(
+                ctx,
+                Target::Domain,
+                tr!("Domain {0}", domain_pattern(&address)),
+            ))
+            .with_child(self.target_button(ctx, Target::Custom, tr!("Other Pattern")));
+
+        let mut panel = Column::new()
+            .padding(2)
+            .gap(2)
+            .min_width("min(30rem, 80vw)")
+            .with_child(
+                Container::new()
+                    .class("pwt-text-truncate")
+                    .class(Opacity::Half)
+                    .with_child(tr!("Sender: {0}", props.from)),
+            )
+            .with_child(targets);
+
+        if self.target == Target::Custom {
+            panel.add_child(
+                Column::new()
+                    .gap(1)
+                    .with_child(
+                        Container::new()
+                            .class("pwt-font-label-small")
+                            .with_child(tr!("Address or pattern, '*' matches any text")),
+                    )
+                    .with_child(Field::new().name("pattern").default(address.clone())),
+            );
+        }
+
+        let followup_label = match props.list {
+            SenderList::Welcomelist => tr!("Also deliver this mail"),
+            SenderList::Blocklist => tr!("Also delete this mail"),
+        };
+        let label_id = get_unique_element_id();
+        panel.add_child(
+            Row::new()
+                .gap(1)
+                .class(AlignItems::Center)
+                .with_child(
+                    Checkbox::new()
+                        .label_id(label_id.clone())
+                        .checked(self.followup)
+                        .on_change(ctx.link().callback(Msg::Followup)),
+                )
+                .with_child(FieldLabel::new(followup_label).id(label_id)),
+        );
+
+        if let Some(error) = &self.error {
+            panel.add_child(error_message(error));
+        }
+
+        let on_close = props.on_close.clone();
+        panel.add_child(
+            Row::new()
+                .gap(1)
+                .class(JustifyContent::FlexEnd)
+                .with_child(
+                    Button::new(tr!("Cancel"))
+                        .class("pwt-button-text")
+                        .on_activate({
+                            let on_close = on_close.clone();
+                            move |_| {
+                                if let Some(on_close) = &on_close {
+                                    on_close.emit(());
+                                }
+                            }
+                        }),
+                )
+                .with_child(
+                    Button::new(props.list.label())
+                        .class("pwt-scheme-primary")
+                        .disabled(self.submitting)
+                        .on_activate(ctx.link().callback(|_| Msg::Submit)),
+                ),
+        );
+
+        Dialog::new(title)
+            .on_close(move |_| {
+                if let Some(on_close) = &on_close {
+                    on_close.emit(());
+                }
+            })
+            .with_child(
+                Form::new()
+                    .form_context(self.form_context.clone())
+                    .with_child(panel),
+            )
+            .into()
+    }
+}
+
+impl From<ListDialog> for VNode {
+    fn from(val: ListDialog) -> Self {
+        let comp = VComp::new::<PmgListDialog>(Rc::new(val), None);
+        VNode::from(comp)
+    }
+}
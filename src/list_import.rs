@@ -0,0 +1,121 @@
+use std::collections::HashSet;
+
+use crate::sender_lists::valid_entry;
+
+/// How an imported entry relates to the list it is imported into.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum ImportStatus {
+    New,
+    Duplicate,
+    Invalid,
+}
+
+#[derive(Clone, PartialEq)]
+pub(crate) struct ImportEntry {
+    pub value: String,
+    pub status: ImportStatus,
+}
+
+/// Extracts addresses from an uploaded file and classifies them against the
+/// current list entries.
+///
+/// vCard files are recognized by content, CSV files by name or separator.
+/// Anything else is read as plain text with one address per line.
+pub(crate) fn parse_import(
+    file_name: &str,
+    content: &str,
+    existing: &[String],
+) -> Vec<ImportEntry> {
+    let raw = if content.contains("BEGIN:VCARD") {
+        parse_vcard(content)
+    } else if file_name.to_lowercase().ends_with(".csv")
+        || content.lines().any(|line| line.contains([',', ';']))
+    {
+        parse_csv(content)
+    } else {
+        parse_text(content)
+    };
+
+    let mut seen: HashSet<String> = existing.iter().map(|entry| entry.to_lowercase()).collect();
+    raw.into_iter()
+        .map(|value| {
+            let value = value.trim().to_lowercase();
+            let status = if !valid_entry(&value) {
+                ImportStatus::Invalid
+            } else if !seen.insert(value.clone()) {
+                ImportStatus::Duplicate
+            } else {
+                ImportStatus::New
+            };
+            ImportEntry { value, status }
+        })
+        .collect()
+}
+
+fn parse_text(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect()
+}
+
+// Takes the first field that looks like an address from every row, else the
+// first that looks like a domain. A first row without either is considered a
+// header, later ones are kept whole to show up as invalid.
+fn parse_csv(content: &str) -> Vec<String> {
+    let mut res = Vec::new();
+    for (pos, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line
+            .split([',', ';', '\t'])
+            .map(|field| field.trim().trim_matches('"').trim())
+            .collect();
+        let value = fields
+            .iter()
+            .find(|field| field.contains('@'))
+            .or_else(|| fields.iter().find(|field| valid_entry(field)));
+        match value {
+            Some(value) => res.push(value.to_string()),
+            None if pos == 0 => {}
+            None => res.push(line.trim().to_string()),
+        }
+    }
+    res
+}
+
+// Collects the EMAIL properties of all contacts, e.g. `EMAIL;TYPE=work:a@b.c`.
+fn parse_vcard(content: &str) -> Vec<String> {
+    // long lines are folded by a line break followed by a space or tab
+    let unfolded = content
+        .replace("\r\n", "\n")
+        .replace("\n ", "")
+        .replace("\n\t", "");
+    unfolded
+        .lines()
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            // properties may be grouped, as in `item1.EMAIL`
+            let name = name.split(';').next()?.rsplit('.').next()?;
+            name.eq_ignore_ascii_case("EMAIL")
+                .then(|| value.trim().to_string())
+        })
+        .collect()
+}
+
+/// Serializes list entries as CSV with a header row.
+pub(crate) fn export_csv(entries: &[String]) -> String {
+    let mut csv = String::from("address\n");
+    for entry in entries {
+        if entry.contains([',', '"', '\n']) {
+            csv.push_str(&format!("\"{}\"\n", entry.replace('"', "\"\"")));
+        } else {
+            csv.push_str(entry);
+            csv.push('\n');
+        }
+    }
+    csv
+}
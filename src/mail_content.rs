@@ -0,0 +1,418 @@
+use anyhow::{bail, Error};
+use js_sys::Reflect;
+use serde_json::{json, Value};
+use wasm_bindgen::JsValue;
+use web_sys::HtmlIFrameElement;
+
+use crate::session::http_get;
+
+// Links of the mail lead to a document with a frame of this name followed by
+// the index of the link. The sandbox keeps the app from looking into the mail
+// document, but it may still ask for the frames it contains by their name.
+const LINK_FRAME_PREFIX: &str = "pmg-link-";
+
+/// A mail as the server renders it.
+pub(crate) struct MailContent {
+    /// The HTML rendering, the server only adds external images if asked to.
+    pub html: String,
+    /// The rest of the answer, e.g. the flags of the mail.
+    pub data: Value,
+}
+
+pub(crate) async fn load_mail_content(id: &str, load_images: bool) -> Result<MailContent, Error> {
+    let mut param = json!({ "id": id });
+    if load_images {
+        param["images"] = json!(1);
+    }
+    let mut data: Value = http_get("/quarantine/content", Some(param)).await?;
+    let html = match data["content"].take() {
+        Value::String(html) => html,
+        _ => bail!("mail has no content"),
+    };
+    Ok(MailContent { html, data })
+}
+
+// Nothing but inline styles and embedded resources, plus external images if
+// the user asked for them. Complements the sandbox of the iframe, which
+// already blocks scripts, forms and access to the app.
+fn content_security_policy(load_images: bool) -> String {
+    let img_src = if load_images {
+        "data: cid: http: https:"
+    } else {
+        "data: cid:"
+    };
+    format!(
+        "default-src 'none'; img-src {img_src}; style-src 'unsafe-inline'; \
+         font-src data:; form-action 'none'; base-uri 'none'"
+    )
+}
+
+/// A link of the mail.
+#[derive(Clone, PartialEq)]
+pub(crate) struct MailLink {
+    pub href: String,
+    /// What the mail shows as the link.
+    pub text: String,
+}
+
+/// The HTML of a mail prepared for the `srcdoc` of a sandboxed iframe.
+pub(crate) struct SandboxedDocument {
+    pub srcdoc: String,
+    /// The links of the mail, their targets in the document are placeholders.
+    pub links: Vec<MailLink>,
+}
+
+/// Prepares the HTML of a mail for the `srcdoc` of a sandboxed iframe, by
+/// putting a strict content security policy in front of it and replacing the
+/// targets of its links, see [`activated_link`].
+pub(crate) fn sandboxed_document(html: &str, load_images: bool) -> SandboxedDocument {
+    let meta = format!(
+        r#"<meta http-equiv="Content-Security-Policy" content="{}">"#,
+        content_security_policy(load_images)
+    );
+    let (html, links) = replace_links(html);
+
+    // The policy must come before anything it restricts, so it goes right
+    // after the doctype. The parser puts it into the head it creates then, and
+    // merges the mail's own <html> and <head> into it.
+    let trimmed = html.trim_start();
+    let doctype_end = trimmed
+        .get(..9)
+        .filter(|start| start.eq_ignore_ascii_case("<!doctype"))
+        .and_then(|_| trimmed.find('>'))
+        .map(|end| end + 1)
+        .unwrap_or(0);
+    let (doctype, rest) = trimmed.split_at(doctype_end);
+    SandboxedDocument {
+        srcdoc: format!("{doctype}{meta}{rest}"),
+        links,
+    }
+}
+
+/// The link the mail document in `frame` was left through, if any.
+///
+/// Following a link loads the placeholder document in place of the mail,
+/// which fires the `load` event of the frame.
+pub(crate) fn activated_link(frame: &HtmlIFrameElement, links: &[MailLink]) -> Option<MailLink> {
+    let window = frame.content_window()?;
+    // unknown names throw, as the document is of another origin
+    links
+        .iter()
+        .enumerate()
+        .find(|(index, _)| {
+            let name = format!("{LINK_FRAME_PREFIX}{index}");
+            Reflect::get(&window, &JsValue::from_str(&name)).is_ok_and(|frame| frame.is_object())
+        })
+        .map(|(_, link)| link.clone())
+}
+
+// A document holding nothing but the frame named after the link.
+fn placeholder_href(index: usize) -> String {
+    format!("data:text/html,%3Ciframe%20name%3D{LINK_FRAME_PREFIX}{index}%3E%3C%2Fiframe%3E")
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+// Decodes the character references a link target or text commonly contains.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest.find(';').filter(|end| *end <= 10);
+        let character = end.and_then(|end| match &rest[1..end] {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            name => {
+                let number = name.strip_prefix('#')?;
+                let code = match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => number.parse().ok()?,
+                };
+                char::from_u32(code)
+            }
+        });
+        match (character, end) {
+            (Some(character), Some(end)) => {
+                decoded.push(character);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+// The text of a piece of HTML, without its tags.
+fn html_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        match rest[start..].find('>') {
+            Some(end) => rest = &rest[start + end + 1..],
+            None => rest = "",
+        }
+    }
+    text.push_str(rest);
+    decode_entities(&text)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// A start tag, split into its name and attributes, the latter with their
+// values still encoded.
+struct Tag<'a> {
+    name: &'a str,
+    attributes: Vec<(&'a str, Option<&'a str>)>,
+    // length of the tag in the document
+    len: usize,
+}
+
+fn parse_tag(html: &str) -> Option<Tag<'_>> {
+    let inner = html.strip_prefix('<')?;
+    let name_len = inner
+        .find(|c: char| c.is_ascii_whitespace() || c == '>' || c == '/')
+        .unwrap_or(inner.len());
+    let name = &inner[..name_len];
+    if name.is_empty() || !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let mut attributes = Vec::new();
+    let mut rest = &inner[name_len..];
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == '/');
+        if let Some(after) = rest.strip_prefix('>') {
+            let len = html.len() - after.len();
+            return Some(Tag {
+                name,
+                attributes,
+                len,
+            });
+        }
+        let attr_len = rest
+            .find(|c: char| c.is_ascii_whitespace() || matches!(c, '=' | '>' | '/'))
+            .unwrap_or(rest.len());
+        if attr_len == 0 {
+            // unterminated tag
+            return None;
+        }
+        let attr = &rest[..attr_len];
+        rest = rest[attr_len..].trim_start();
+        let Some(after) = rest.strip_prefix('=') else {
+            attributes.push((attr, None));
+            continue;
+        };
+        let after = after.trim_start();
+        let (value, after) = match after.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let end = after[1..].find(quote)?;
+                (&after[1..end + 1], &after[end + 2..])
+            }
+            _ => {
+                let end = after
+                    .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                    .unwrap_or(after.len());
+                after.split_at(end)
+            }
+        };
+        attributes.push((attr, Some(value)));
+        rest = after;
+    }
+}
+
+// Link targets, including the `xlink:href` of links in SVG images.
+fn is_href(attribute: &str) -> bool {
+    let local = attribute.rsplit(':').next().unwrap_or(attribute);
+    local.eq_ignore_ascii_case("href")
+}
+
+// SVG animations may set the target of a link after we replaced it.
+fn animates_href(tag: &Tag) -> bool {
+    (tag.name.eq_ignore_ascii_case("set") || tag.name.eq_ignore_ascii_case("animate"))
+        && tag.attributes.iter().any(|(name, value)| {
+            name.eq_ignore_ascii_case("attributename") && value.is_some_and(is_href)
+        })
+}
+
+// Points the links of the mail to placeholders, and returns their original
+// targets by the index of their placeholder. Also drops the targets of the
+// links, so they open in the frame of the mail, where the app notices them.
+//
+// The links of SVG images may carry their target as `xlink:href`, the
+// placeholder goes into `href`, which takes precedence.
+fn replace_links(html: &str) -> (String, Vec<MailLink>) {
+    let mut document = String::with_capacity(html.len());
+    let mut links = Vec::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        document.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if rest.starts_with("<!--") {
+            let end = rest.find("-->").map_or(rest.len(), |end| end + 3);
+            document.push_str(&rest[..end]);
+            rest = &rest[end..];
+            continue;
+        }
+        let tag = match parse_tag(rest) {
+            Some(tag) if animates_href(&tag) => {
+                rest = &rest[tag.len..];
+                continue;
+            }
+            Some(tag)
+                if tag.name.eq_ignore_ascii_case("a") || tag.name.eq_ignore_ascii_case("area") =>
+            {
+                tag
+            }
+            _ => {
+                document.push('<');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+        let href = tag
+            .attributes
+            .iter()
+            .filter(|(name, value)| is_href(name) && value.is_some())
+            .min_by_key(|(name, _)| !name.eq_ignore_ascii_case("href"))
+            .and_then(|(_, value)| *value);
+        let Some(href) = href else {
+            document.push_str(&rest[..tag.len]);
+            rest = &rest[tag.len..];
+            continue;
+        };
+
+        let text = if tag.name.eq_ignore_ascii_case("a") {
+            let content = &rest[tag.len..];
+            let end = find_ignore_case(content, "</a").unwrap_or(content.len());
+            html_text(&content[..end])
+        } else {
+            tag.attributes
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case("alt"))
+                .and_then(|(_, value)| value.map(html_text))
+                .unwrap_or_default()
+        };
+
+        document.push('<');
+        document.push_str(tag.name);
+        for (name, value) in &tag.attributes {
+            if is_href(name) || name.eq_ignore_ascii_case("target") {
+                continue;
+            }
+            document.push(' ');
+            document.push_str(name);
+            if let Some(value) = value {
+                document.push_str(&format!("=\"{}\"", value.replace('"', "&quot;")));
+            }
+        }
+        document.push_str(&format!(" href=\"{}\">", placeholder_href(links.len())));
+        links.push(MailLink {
+            href: decode_entities(href.trim()),
+            text,
+        });
+        rest = &rest[tag.len..];
+    }
+    document.push_str(rest);
+    (document, links)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_entities_of_links() {
+        assert_eq!(
+            decode_entities("https://example.com/?a=1&amp;b=2"),
+            "https://example.com/?a=1&b=2"
+        );
+        assert_eq!(decode_entities("&lt;&#65;&#x42;&gt;&quot;"), "<AB>\"");
+        assert_eq!(decode_entities("&amp;amp;"), "&amp;");
+        // unknown or unterminated references stay as they are
+        assert_eq!(decode_entities("a&b; c&d"), "a&b; c&d");
+        assert_eq!(decode_entities("&#xZZ; &#1114112;"), "&#xZZ; &#1114112;");
+        assert_eq!(decode_entities("&"), "&");
+    }
+
+    #[test]
+    fn replace_links_with_placeholders() {
+        let (html, links) = replace_links(
+            r#"<p>See <A HREF="https://example.com/?a=1&amp;b=2" target="_blank" class=x>the <b>offer</b></a>"#,
+        );
+        assert_eq!(
+            html,
+            format!(
+                r#"<p>See <A class="x" href="{}">the <b>offer</b></a>"#,
+                placeholder_href(0)
+            )
+        );
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].href, "https://example.com/?a=1&b=2");
+        assert_eq!(links[0].text, "the offer");
+    }
+
+    #[test]
+    fn replace_links_of_areas_and_svg() {
+        let (html, links) = replace_links(
+            r#"<area alt="A &amp; B" href='https://a.example'><svg><a xlink:href="https://b.example"><text>B</text></a></svg>"#,
+        );
+        assert_eq!(
+            html,
+            format!(
+                r#"<area alt="A &amp; B" href="{}"><svg><a href="{}"><text>B</text></a></svg>"#,
+                placeholder_href(0),
+                placeholder_href(1)
+            )
+        );
+        let links: Vec<_> = links
+            .iter()
+            .map(|link| (link.href.as_str(), link.text.as_str()))
+            .collect();
+        assert_eq!(
+            links,
+            [("https://a.example", "A & B"), ("https://b.example", "B")]
+        );
+    }
+
+    #[test]
+    fn replace_links_prefers_href_over_xlink() {
+        let (_, links) = replace_links(
+            r#"<a xlink:href="https://old.example" href="https://new.example">x</a>"#,
+        );
+        assert_eq!(links[0].href, "https://new.example");
+    }
+
+    #[test]
+    fn replace_links_drops_animated_targets() {
+        let (html, links) = replace_links(
+            r#"<svg><a href="https://a.example"><set attributeName="href" to="https://evil.example"/>x</a></svg>"#,
+        );
+        assert!(!html.contains("evil"));
+        assert_eq!(links.len(), 1);
+    }
+
+    #[test]
+    fn replace_links_skips_other_markup() {
+        let html = r#"<!-- <a href="https://hidden.example"> --><a name="top">top</a> 1 < 2 <img src="cid:x">"#;
+        let (replaced, links) = replace_links(html);
+        assert_eq!(replaced, html);
+        assert!(links.is_empty());
+    }
+}
@@ -1,5 +1,6 @@
 mod spam_list;
 
+use spam_list::MailInfo;
 pub use spam_list::SpamList;
 
 mod page_mail_view;
@@ -14,57 +15,253 @@ pub use page_not_found::PageNotFound;
 mod page_login;
 pub use page_login::PageLogin;
 
-use anyhow::{format_err, Error};
+mod page_action_result;
+use page_action_result::PageActionResult;
+
+mod page_session_expired;
+use page_session_expired::PageSessionExpired;
+
+mod page_statistics;
+pub use page_statistics::PageStatistics;
+
+mod page_sender_lists;
+pub use page_sender_lists::PageSenderLists;
+
+mod a11y;
+use a11y::announce;
+
+mod action_sheet;
+
+mod calendar;
+
+mod date_format;
+use date_format::clear_format_cache;
+
+mod history;
+
+mod idle;
+use idle::IdleWatcher;
+
+mod keyboard;
+
+mod list_dialog;
+
+mod link_sheet;
+
+mod list_import;
+
+mod mail_content;
+
+mod notifications;
+use notifications::MailNotifier;
+
+mod oidc;
+
+mod sender_lists;
+
+mod sender_sheet;
+
+mod session;
+use session::{end_session, log_out, logged_out, start_session, LoginSession};
+
+mod settings;
+
+mod tab_sync;
+use tab_sync::{broadcast, TabEvent, TabSubscription};
+
+use std::time::Duration;
+
+use anyhow::{bail, format_err, Error};
 use gloo_utils::format::JsValueSerdeExt;
+use gloo_utils::window;
 use serde::Deserialize;
 use serde_json::{json, Value};
-use wasm_bindgen::JsValue;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::MediaQueryList;
+use yew::platform::time::sleep;
 use yew::prelude::*;
+use yew_router::history::{BrowserHistory, History};
 use yew_router::Routable;
 
+use pwt::css::JustifyContent;
+use pwt::prelude::*;
 use pwt::state::SharedState;
 use pwt::touch::MaterialApp;
+use pwt::widget::{Button, Column, Dialog, Row};
 
 use proxmox_login::Authentication;
 use proxmox_yew_comp::{
-    authentication_from_cookie, available_language_list, http_post, http_set_auth,
-    register_auth_observer, stop_ticket_refresh_loop, AuthObserver, ExistingProduct,
+    authentication_from_cookie, available_language_list, register_auth_observer, AuthObserver,
+    ExistingProduct,
 };
 
 // Note: The server provides this data with the template
-#[derive(Deserialize)]
+#[derive(Clone, PartialEq, Deserialize)]
 #[allow(non_snake_case)]
 pub struct ServerConfig {
     pub i18nVersion: String,
     pub uiVersion: String,
     pub basePath: String,
+    // user the page was rendered for, empty if not logged in
+    #[serde(default)]
+    pub UserName: String,
+    #[serde(default)]
+    pub CSRFPreventionToken: String,
+    // default idle timeout in minutes, 0 for none
+    #[serde(default)]
+    pub IdleTimeout: u32,
+    // days the links of spam reports stay valid, 0 if unknown
+    #[serde(default)]
+    pub QuarantineLifetime: u32,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            i18nVersion: String::new(),
+            uiVersion: String::new(),
+            basePath: String::from("/mobile"),
+            UserName: String::new(),
+            CSRFPreventionToken: String::new(),
+            IdleTimeout: 0,
+            QuarantineLifetime: 0,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Reads and checks the configuration in `window.Proxmox`.
+    fn load() -> Result<Self, Error> {
+        let value = js_sys::Reflect::get(&window(), &JsValue::from_str("Proxmox"))
+            .map_err(|err| format_err!("could not read window.Proxmox: {err:?}"))?;
+        if value.is_undefined() || value.is_null() {
+            bail!("window.Proxmox is not defined, the page was not served from the index template");
+        }
+        let config: Self = JsValueSerdeExt::into_serde(&value)
+            .map_err(|err| format_err!("window.Proxmox is malformed: {err}"))?;
+
+        for (name, value) in [
+            ("i18nVersion", &config.i18nVersion),
+            ("uiVersion", &config.uiVersion),
+            ("basePath", &config.basePath),
+            ("UserName", &config.UserName),
+            ("CSRFPreventionToken", &config.CSRFPreventionToken),
+        ] {
+            if value.contains("[%") {
+                bail!("window.Proxmox.{name} was not filled in by the server: '{value}'");
+            }
+        }
+        if !config.basePath.starts_with('/') {
+            bail!(
+                "window.Proxmox.basePath is not an absolute path: '{}'",
+                config.basePath
+            );
+        }
+        if config.UserName.is_empty() != config.CSRFPreventionToken.is_empty() {
+            bail!("window.Proxmox has only one of UserName and CSRFPreventionToken");
+        }
+        Ok(config)
+    }
+
+    /// Session of the user the page was rendered for. With HttpOnly cookies
+    /// the app cannot read the ticket, the browser sends it along instead.
+    fn http_only_session(&self) -> Option<LoginSession> {
+        (!self.UserName.is_empty()).then(|| LoginSession::HttpOnly {
+            userid: self.UserName.clone(),
+            csrf_token: self.CSRFPreventionToken.clone(),
+        })
+    }
+
+    /// Seconds the tickets of spam report links stay valid, if known.
+    fn quarantine_lifetime(&self) -> Option<i64> {
+        (self.QuarantineLifetime > 0).then(|| self.QuarantineLifetime as i64 * 86400)
+    }
+
+    /// URL of an image shipped with the app.
+    pub(crate) fn image_url(&self, name: &str) -> String {
+        format!("{}/images/{name}", self.basePath)
+    }
+
+    fn versioned_url(url: String, version: &str) -> String {
+        if version.is_empty() {
+            url
+        } else {
+            format!("{url}?v{version}")
+        }
+    }
 }
 
 pub enum Msg {
     Login(Authentication),
     Logout,
+    SessionExpired,
+    LoginAgain,
+    IdleWarning(Option<i64>), // seconds left
+    IdleTimeout,
+    TabEvent(TabEvent),
+    SyncReload,
+    TwoPane(bool), // on/off
 }
 
+// how long to wait for more changes from other tabs before reloading
+const SYNC_RELOAD_DELAY: Duration = Duration::from_millis(300);
+
+// above this width the list and the mail preview are shown side by side
+const TWO_PANE_MEDIA_QUERY: &str = "(min-width: 900px)";
+
 #[derive(Clone, Routable, PartialEq)]
 enum Route {
     #[at("/")]
     SpamList,
     #[at("/post/:id")]
     ViewMail { id: String },
+    #[at("/statistics")]
+    Statistics,
+    #[at("/lists")]
+    SenderLists,
+    #[at("/action/:action/:id")]
+    ActionResult { action: MailAction, id: String },
     #[not_found]
     #[at("/404")]
     NotFound,
 }
 
-fn switch(route: &str) -> Vec<Html> {
+fn switch(route: &str, two_pane: bool) -> Vec<Html> {
     let routes = Routable::recognize(route).unwrap();
 
     match routes {
         Route::SpamList => {
-            vec![PageSpamList::new().into()]
+            vec![PageSpamList::new().two_pane(two_pane).into()]
+        }
+        // on wide screens the list page shows the preview itself, so the
+        // selection stays visible next to it
+        Route::ViewMail { id } if two_pane => {
+            vec![PageSpamList::new().two_pane(true).selected(id).into()]
         }
         Route::ViewMail { id } => {
-            vec![PageSpamList::new().into(), PageMailView::new(id).into()]
+            vec![
+                PageSpamList::new().selected(id.clone()).into(),
+                PageMailView::new(id).keyboard(true).into(),
+            ]
+        }
+        Route::Statistics => {
+            vec![
+                PageSpamList::new().two_pane(two_pane).into(),
+                PageStatistics::new().into(),
+            ]
+        }
+        Route::SenderLists => {
+            vec![
+                PageSpamList::new().two_pane(two_pane).into(),
+                PageSenderLists::new().into(),
+            ]
+        }
+        Route::ActionResult { action, id } => {
+            vec![
+                PageSpamList::new().two_pane(two_pane).into(),
+                PageActionResult::new(id, action).into(),
+            ]
         }
         Route::NotFound => {
             vec![html! { <PageNotFound/> }]
@@ -73,10 +270,150 @@ fn switch(route: &str) -> Vec<Html> {
 }
 
 struct PmgQuarantineApp {
-    login_info: Option<Authentication>,
+    login_info: Option<LoginSession>,
+    // the server no longer accepts the session
+    session_expired: bool,
     _auth_observer: AuthObserver,
-    server_config: Option<ServerConfig>,
+    server_config: ServerConfig,
     reload: QuarantineReload,
+    loaded: LoadedMails,
+    notifier: Option<MailNotifier>,
+    idle: Option<IdleWatcher>,
+    // seconds until the idle logout, while warning about it
+    idle_countdown: Option<i64>,
+    _tab_subscription: TabSubscription,
+    sync_reload_pending: bool,
+    two_pane: bool,
+    // kept alive to keep the layout breakpoint listener registered
+    _two_pane_listener: Option<(MediaQueryList, Closure<dyn Fn()>)>,
+}
+
+impl PmgQuarantineApp {
+    fn login(&mut self, ctx: &Context<Self>, info: Authentication) {
+        let login = LoginSession::Ticket(info);
+        self.session_expired = false;
+        start_session(
+            &login,
+            self.server_config.quarantine_lifetime(),
+            ctx.link().callback(|_| Msg::SessionExpired),
+            ctx.link().callback(|_| Msg::Logout),
+        );
+        self.login_info = Some(login);
+        self.notifier = Some(MailNotifier::start());
+        self.idle = Some(Self::watch_idle(ctx, &self.server_config));
+    }
+
+    fn tab_event(&mut self, ctx: &Context<Self>, event: TabEvent) -> bool {
+        match event {
+            TabEvent::MailAction { ids, action } => {
+                if matches!(action.parse(), Ok(MailAction::Deliver | MailAction::Delete)) {
+                    self.loaded.0.write().retain(|mail| !ids.contains(&mail.id));
+                }
+                self.schedule_sync_reload(ctx);
+                false
+            }
+            TabEvent::Reload => {
+                self.schedule_sync_reload(ctx);
+                false
+            }
+            // the pages using them listen themselves, this runs first as the
+            // app subscribed before them
+            TabEvent::Settings => {
+                clear_format_cache();
+                false
+            }
+            TabEvent::Login { userid } => {
+                if self.login_info.as_ref().map(LoginSession::userid) == Some(userid.as_str()) {
+                    return false;
+                }
+                match authentication_from_cookie(&ExistingProduct::PMG) {
+                    Some(info) if info.userid == userid => {
+                        self.login(ctx, info);
+                        true
+                    }
+                    // HttpOnly cookie, the server provides the session with the page
+                    _ => {
+                        let _ = window().location().reload();
+                        false
+                    }
+                }
+            }
+            TabEvent::Logout => {
+                if self.login_info.is_some() {
+                    log_out();
+                }
+                false
+            }
+        }
+    }
+
+    // An action in another tab is usually followed by the reload it causes,
+    // so reload once for both.
+    fn schedule_sync_reload(&mut self, ctx: &Context<Self>) {
+        if self.sync_reload_pending {
+            return;
+        }
+        self.sync_reload_pending = true;
+        let link = ctx.link().clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            sleep(SYNC_RELOAD_DELAY).await;
+            link.send_message(Msg::SyncReload);
+        });
+    }
+
+    fn watch_idle(ctx: &Context<Self>, config: &ServerConfig) -> IdleWatcher {
+        IdleWatcher::start(
+            config.IdleTimeout,
+            ctx.link().callback(Msg::IdleWarning),
+            ctx.link().callback(|_| Msg::IdleTimeout),
+        )
+    }
+
+    fn idle_dialog(&self, ctx: &Context<Self>) -> Option<Html> {
+        let seconds = self.idle_countdown?;
+        let dialog = Dialog::new(tr!("Are you still there?"))
+            // any input counts as activity, closing is enough to stay
+            .on_close(ctx.link().callback(|_| Msg::IdleWarning(None)))
+            .with_child(
+                Column::new()
+                    .padding(2)
+                    .gap(2)
+                    .with_child(tr!(
+                        "You will be logged out in {0} seconds due to inactivity.",
+                        seconds
+                    ))
+                    .with_child(
+                        Row::new()
+                            .gap(1)
+                            .class(JustifyContent::FlexEnd)
+                            .with_child(
+                                Button::new(tr!("Log Out"))
+                                    .on_activate(ctx.link().callback(|_| Msg::IdleTimeout)),
+                            )
+                            .with_child(
+                                Button::new(tr!("Stay Logged In"))
+                                    .class("pwt-scheme-primary")
+                                    .on_activate(ctx.link().callback(|_| Msg::IdleWarning(None))),
+                            ),
+                    ),
+            );
+        Some(dialog.into())
+    }
+
+    fn watch_two_pane(ctx: &Context<Self>) -> Option<(MediaQueryList, Closure<dyn Fn()>)> {
+        let query = window().match_media(TWO_PANE_MEDIA_QUERY).ok().flatten()?;
+        let listener = Closure::<dyn Fn()>::new({
+            let query = query.clone();
+            let link = ctx.link().clone();
+            move || link.send_message(Msg::TwoPane(query.matches()))
+        });
+        if let Err(err) =
+            query.add_event_listener_with_callback("change", listener.as_ref().unchecked_ref())
+        {
+            log::error!("could not watch layout breakpoint: {err:?}");
+        }
+        Some((query, listener))
+    }
 }
 
 impl Component for PmgQuarantineApp {
@@ -84,94 +421,172 @@ impl Component for PmgQuarantineApp {
     type Properties = ();
 
     fn create(ctx: &Context<Self>) -> Self {
-        let mut server_config = None;
-        if let Some(window) = web_sys::window() {
-            if let Ok(value) = js_sys::Reflect::get(&window, &JsValue::from_str("Proxmox")) {
-                if let Ok(config) = JsValueSerdeExt::into_serde::<ServerConfig>(&value) {
-                    server_config = Some(config);
-                }
-            }
-        }
-        // set auth info from cookie
-        let login_info = authentication_from_cookie(&ExistingProduct::PMG);
+        let server_config = ServerConfig::load().unwrap_or_else(|err| {
+            log::error!("invalid server configuration, using defaults: {err}");
+            ServerConfig::default()
+        });
+        // set auth info from cookie, or from the template if that cookie is
+        // HttpOnly and the user did not log out of it
+        let login_info = authentication_from_cookie(&ExistingProduct::PMG)
+            .map(LoginSession::Ticket)
+            .or_else(|| {
+                server_config
+                    .http_only_session()
+                    .filter(|login| !logged_out(login.userid()))
+            });
         if let Some(login_info) = &login_info {
-            http_set_auth(login_info.clone());
-            if login_info.ticket.to_string().starts_with("PMGQUAR:") {
-                stop_ticket_refresh_loop();
-            }
+            start_session(
+                login_info,
+                server_config.quarantine_lifetime(),
+                ctx.link().callback(|_| Msg::SessionExpired),
+                ctx.link().callback(|_| Msg::Logout),
+            );
+            // already logged in, follow a spam report link right away
+            page_login::leave_quarantine_path();
         }
+        let idle = login_info
+            .as_ref()
+            .map(|_| Self::watch_idle(ctx, &server_config));
+        let two_pane_listener = Self::watch_two_pane(ctx);
+        let two_pane = two_pane_listener
+            .as_ref()
+            .is_some_and(|(query, _)| query.matches());
+
         let _auth_observer = register_auth_observer(
             ctx.link()
                 .batch_callback(|logout: bool| logout.then_some(Msg::Logout)),
         );
         Self {
+            notifier: login_info.is_some().then(MailNotifier::start),
             login_info,
+            idle,
+            idle_countdown: None,
+            _tab_subscription: TabSubscription::new(ctx.link().callback(Msg::TabEvent)),
+            sync_reload_pending: false,
+            session_expired: false,
             _auth_observer,
             server_config,
             reload: QuarantineReload(SharedState::new(0)),
+            loaded: LoadedMails(SharedState::new(Vec::new())),
+            two_pane,
+            _two_pane_listener: two_pane_listener,
         }
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
         let link = ctx.link().clone();
         let logged_in = self.login_info.is_some();
+        let two_pane = self.two_pane;
+        let session_expired = self.session_expired;
+        // quarantine users are named after their address
+        let mail = self.login_info.as_ref().and_then(|info| {
+            info.userid()
+                .strip_suffix("@quarantine")
+                .map(|mail| mail.to_string())
+        });
         let app = MaterialApp::new(move |path: &str| {
-            if logged_in {
-                switch(path)
+            if logged_in && session_expired {
+                vec![PageSessionExpired::new()
+                    .mail(mail.clone())
+                    .on_login(link.callback(|_| Msg::LoginAgain))
+                    .into()]
+            } else if logged_in {
+                switch(path, two_pane)
             } else {
                 vec![PageLogin::new().on_login(link.callback(Msg::Login)).into()]
             }
         })
         .theme_url_builder({
-            let ui_version = self.server_config.as_ref().map(|c| c.uiVersion.clone());
-            let base_path = self
-                .server_config
-                .as_ref()
-                .map(|c| c.basePath.clone())
-                .unwrap_or("/mobile".into());
+            let config = self.server_config.clone();
             move |theme: &String| {
-                let url = format!("{base_path}/css/{}-yew-style.css", theme.to_lowercase());
-                if let Some(version) = &ui_version {
-                    format!("{url}?v{version}")
-                } else {
-                    url
-                }
+                let url = format!(
+                    "{}/css/{}-yew-style.css",
+                    config.basePath,
+                    theme.to_lowercase()
+                );
+                ServerConfig::versioned_url(url, &config.uiVersion)
             }
         })
         .catalog_url_builder({
-            let i18n_version = self.server_config.as_ref().map(|c| c.i18nVersion.clone());
-            let base_path = self
-                .server_config
-                .as_ref()
-                .map(|c| c.basePath.clone())
-                .unwrap_or("/mobile".into());
+            let config = self.server_config.clone();
             move |lang: &String| {
-                let url = format!("{base_path}/i18n/pmg-yew-quarantine-catalog-{lang}.mo");
-                if let Some(version) = &i18n_version {
-                    format!("{url}?v{version}")
-                } else {
-                    url
-                }
+                let url = format!(
+                    "{}/i18n/pmg-yew-quarantine-catalog-{lang}.mo",
+                    config.basePath
+                );
+                ServerConfig::versioned_url(url, &config.i18nVersion)
             }
         });
 
         html! {
-            <ContextProvider<QuarantineReload> context={self.reload.clone()}>
-                { Html::from(app) }
-            </ContextProvider<QuarantineReload>>
+            <ContextProvider<ServerConfig> context={self.server_config.clone()}>
+                <ContextProvider<QuarantineReload> context={self.reload.clone()}>
+                    <ContextProvider<LoadedMails> context={self.loaded.clone()}>
+                        { Html::from(app) }
+                        { self.idle_dialog(ctx) }
+                    </ContextProvider<LoadedMails>>
+                </ContextProvider<QuarantineReload>>
+            </ContextProvider<ServerConfig>>
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Msg::Login(info) => {
-                self.login_info = Some(info.clone());
-                http_set_auth(info.clone());
-                if info.ticket.to_string().starts_with("PMGQUAR:") {
-                    stop_ticket_refresh_loop();
+                broadcast(TabEvent::Login {
+                    userid: info.userid.clone(),
+                });
+                self.login(ctx, info);
+            }
+            Msg::Logout => {
+                if let Some(login) = self.login_info.take() {
+                    broadcast(TabEvent::Logout);
+                    end_session(login.userid());
+                }
+                self.session_expired = false;
+                self.notifier = None;
+                self.idle = None;
+                self.idle_countdown = None;
+                self.loaded.0.write().clear();
+            }
+            Msg::SessionExpired => {
+                if self.session_expired || self.login_info.is_none() {
+                    return false;
+                }
+                self.session_expired = true;
+                // polling would only fail from now on
+                self.notifier = None;
+            }
+            Msg::LoginAgain => {
+                log_out();
+                return false;
+            }
+            Msg::IdleWarning(seconds) => {
+                if seconds.is_some() && self.idle_countdown.is_none() {
+                    announce(&tr!("You will be logged out soon due to inactivity"));
                 }
+                self.idle_countdown = seconds;
+            }
+            Msg::IdleTimeout => {
+                // the next user starts on the list, not on the last mail
+                BrowserHistory::new().replace("/");
+                announce(&tr!("Logged out due to inactivity"));
+                // Msg::Logout clears the loaded data
+                log_out();
+                return false;
+            }
+            Msg::TabEvent(event) => return self.tab_event(ctx, event),
+            Msg::SyncReload => {
+                self.sync_reload_pending = false;
+                // only locally, bump() would tell the other tabs again
+                **self.reload.0.write() += 1;
+                return false;
+            }
+            Msg::TwoPane(two_pane) => {
+                let changed = self.two_pane != two_pane;
+                self.two_pane = two_pane;
+                return changed;
             }
-            Msg::Logout => self.login_info = None,
         }
         true
     }
@@ -183,6 +598,19 @@ impl Component for PmgQuarantineApp {
 #[derive(Clone, PartialEq)]
 pub(crate) struct QuarantineReload(pub SharedState<usize>);
 
+impl QuarantineReload {
+    /// Triggers a reload in this and all other tabs.
+    pub(crate) fn bump(&self) {
+        **self.0.write() += 1;
+        broadcast(TabEvent::Reload);
+    }
+}
+
+/// The mails the spam list currently shows, so views on top of it can relate
+/// a mail to the rest of the quarantine without loading it again.
+#[derive(Clone, PartialEq)]
+pub(crate) struct LoadedMails(pub SharedState<Vec<MailInfo>>);
+
 #[derive(Clone, Copy, PartialEq)]
 pub(crate) enum MailAction {
     Deliver,
@@ -193,6 +621,48 @@ pub(crate) enum MailAction {
     MarkUnseen,
 }
 
+impl MailAction {
+    pub(crate) fn label(&self) -> String {
+        match self {
+            MailAction::Deliver => tr!("Deliver"),
+            MailAction::Delete => tr!("Delete"),
+            MailAction::Welcomelist => tr!("Welcomelist"),
+            MailAction::Blocklist => tr!("Blocklist"),
+            MailAction::MarkSeen => tr!("Mark as Seen"),
+            MailAction::MarkUnseen => tr!("Mark as Unseen"),
+        }
+    }
+
+    /// Question confirming the action for `count` mails at once.
+    pub(crate) fn confirm_text(&self, count: usize) -> String {
+        match self {
+            MailAction::Deliver => tr!("Deliver {0} mails?", count),
+            MailAction::Delete => tr!("Delete {0} mails permanently?", count),
+            MailAction::Welcomelist => tr!(
+                "Add the senders of {0} mails to the welcomelist and deliver the mails?",
+                count
+            ),
+            MailAction::Blocklist => tr!(
+                "Add the senders of {0} mails to the blocklist and delete the mails?",
+                count
+            ),
+            MailAction::MarkSeen => tr!("Mark {0} mails as seen?", count),
+            MailAction::MarkUnseen => tr!("Mark {0} mails as unseen?", count),
+        }
+    }
+
+    pub(crate) fn icon_class(&self) -> &'static str {
+        match self {
+            MailAction::Deliver => "fa fa-paper-plane",
+            MailAction::Delete => "fa fa-trash",
+            MailAction::Welcomelist => "fa fa-check",
+            MailAction::Blocklist => "fa fa-times",
+            MailAction::MarkSeen => "fa fa-eye",
+            MailAction::MarkUnseen => "fa fa-eye-slash",
+        }
+    }
+}
+
 impl std::fmt::Display for MailAction {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         f.write_str(match self {
@@ -226,7 +696,32 @@ pub(crate) async fn mail_action(id: &str, action: MailAction) -> Result<Value, E
         "action": action.to_string(),
         "id": id,
     });
-    http_post("/quarantine/content", Some(param)).await
+    let result = session::http_post("/quarantine/content", Some(param)).await?;
+    broadcast(TabEvent::MailAction {
+        ids: id.split(';').map(String::from).collect(),
+        action: action.to_string(),
+    });
+    Ok(result)
+}
+
+// ids per request of a batch, which keeps requests for large groups or
+// selections small
+const BATCH_SIZE: usize = 50;
+
+/// Runs an action on several mails. The API accepts a semicolon separated
+/// list of ids, large batches are sent in chunks one after the other.
+pub(crate) async fn mail_action_batch(ids: &[String], action: MailAction) -> Result<(), Error> {
+    let mut done = 0;
+    for chunk in ids.chunks(BATCH_SIZE) {
+        if let Err(err) = mail_action(&chunk.join(";"), action).await {
+            return Err(match done {
+                0 => err,
+                _ => format_err!("{err} (after {done} of {} mails)", ids.len()),
+            });
+        }
+        done += chunk.len();
+    }
+    Ok(())
 }
 
 fn main() {
@@ -235,9 +730,7 @@ fn main() {
     pwt::state::set_available_themes(&["Mobile"]);
     pwt::state::set_available_languages(available_language_list());
 
-    pwt::props::set_http_get_method(
-        |url| async move { proxmox_yew_comp::http_get(&url, None).await },
-    );
+    pwt::props::set_http_get_method(|url| async move { session::http_get(url, None).await });
 
     wasm_logger::init(wasm_logger::Config::default());
 
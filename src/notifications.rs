@@ -0,0 +1,193 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use anyhow::Error;
+use gloo_utils::window;
+use js_sys::{Array, Date, Function, Reflect};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Notification, NotificationOptions, NotificationPermission};
+use yew::platform::time::sleep;
+use yew_router::history::{BrowserHistory, History};
+use yew_router::Routable;
+
+use pwt::prelude::*;
+
+use crate::session::session_userid;
+use crate::settings::{load_item, remove_item, store_item, Settings};
+use crate::spam_list::{load_mails, MailInfo};
+use crate::Route;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+// the badge counts unseen mails of the last week, which matches the range the
+// list shows by default
+const BADGE_RANGE: i64 = 7 * 24 * 3600;
+
+// receive time of the newest mail we already notified the user about,
+// followed by the userid as several users may share the browser
+const LAST_NOTIFIED_KEY: &str = "pmg-quarantine-last-notified";
+
+fn last_notified_key() -> Option<String> {
+    session_userid().map(|userid| format!("{LAST_NOTIFIED_KEY}-{userid}"))
+}
+
+/// Forgets the last notified mail, so the next poll announces nothing but
+/// remembers the newest mail. Called when the user opts in, so mails received
+/// while opted out are not announced.
+pub(crate) fn reset_last_notified() {
+    if let Some(key) = last_notified_key() {
+        remove_item(&key);
+    }
+}
+
+/// Polls the quarantine for new mail while the app is open and, if the user
+/// opted in, announces it via browser notifications and the app icon badge.
+///
+/// Polling stops once this is dropped, i.e. on logout.
+pub(crate) struct MailNotifier {
+    alive: Rc<Cell<bool>>,
+}
+
+impl MailNotifier {
+    pub fn start() -> Self {
+        let alive = Rc::new(Cell::new(true));
+        let running = alive.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            while running.get() {
+                // re-read every round so toggling the setting needs no restart
+                if Settings::load().notifications && permission_granted() {
+                    if let Err(err) = poll().await {
+                        log::error!("could not poll quarantine: {err}");
+                    }
+                }
+                sleep(POLL_INTERVAL).await;
+            }
+        });
+        Self { alive }
+    }
+}
+
+impl Drop for MailNotifier {
+    fn drop(&mut self) {
+        self.alive.set(false);
+        clear_badge();
+    }
+}
+
+/// Fetches recent mails, updates the badge and notifies about mails newer
+/// than the last notified one.
+pub(crate) async fn poll() -> Result<(), Error> {
+    let Some(key) = last_notified_key() else {
+        return Ok(());
+    };
+    let now = (Date::now() / 1000.0) as i64;
+    let mut mails = load_mails(Some((now - BADGE_RANGE) as u64), None, None).await?;
+
+    set_badge(mails.iter().filter(|mail| !mail.seen).count());
+
+    let newest = mails.iter().map(|mail| mail.time).max().unwrap_or(now);
+    let last_notified = match load_item(&key).and_then(|v| v.parse::<i64>().ok()) {
+        Some(last_notified) => last_notified,
+        None => {
+            // first poll after opting in, do not announce the whole backlog
+            store_item(&key, &newest.to_string());
+            return Ok(());
+        }
+    };
+
+    mails.retain(|mail| mail.time > last_notified);
+    if mails.is_empty() {
+        return Ok(());
+    }
+    mails.sort_by(|a, b| b.time.cmp(&a.time));
+    store_item(&key, &newest.to_string());
+
+    notify(&mails);
+    Ok(())
+}
+
+// `mails` is sorted newest first and must not be empty
+fn notify(mails: &[MailInfo]) {
+    let newest = &mails[0];
+    let title = if mails.len() == 1 {
+        tr!("New quarantined mail")
+    } else {
+        tr!("{0} new quarantined mails", mails.len())
+    };
+
+    let options = NotificationOptions::new();
+    options.set_body(&format!("{}\n{}", newest.from, newest.subject));
+    // replace a still visible notification instead of stacking them up
+    options.set_tag("pmg-quarantine");
+
+    let notification = match Notification::new_with_options(&title, &options) {
+        Ok(notification) => notification,
+        Err(err) => {
+            log::error!("could not show notification: {err:?}");
+            return;
+        }
+    };
+
+    let path = Route::ViewMail {
+        id: newest.id.clone(),
+    }
+    .to_path();
+    let onclick = Closure::once_into_js({
+        let notification = notification.clone();
+        move || {
+            notification.close();
+            let _ = window().focus();
+            BrowserHistory::new().push(path);
+        }
+    });
+    notification.set_onclick(Some(onclick.unchecked_ref()));
+}
+
+/// Whether the browser offers the Notification API at all (e.g. iOS only
+/// does for installed web apps).
+pub(crate) fn notifications_supported() -> bool {
+    Reflect::has(&window(), &JsValue::from_str("Notification")).unwrap_or(false)
+}
+
+fn permission_granted() -> bool {
+    notifications_supported() && Notification::permission() == NotificationPermission::Granted
+}
+
+/// Asks the user for permission to show notifications, returns whether it was granted.
+pub(crate) async fn request_permission() -> bool {
+    if !notifications_supported() {
+        return false;
+    }
+    match Notification::request_permission() {
+        Ok(promise) => JsFuture::from(promise)
+            .await
+            .ok()
+            .and_then(|value| value.as_string())
+            .is_some_and(|permission| permission == "granted"),
+        Err(_) => false,
+    }
+}
+
+// The Badging API is not available everywhere, so call it dynamically.
+fn set_badge(count: usize) {
+    let navigator = window().navigator();
+    let (method, args) = if count > 0 {
+        ("setAppBadge", Array::of1(&JsValue::from_f64(count as f64)))
+    } else {
+        ("clearAppBadge", Array::new())
+    };
+    if let Ok(func) = Reflect::get(&navigator, &JsValue::from_str(method)) {
+        if let Some(func) = func.dyn_ref::<Function>() {
+            // the returned promise only rejects if the app is not installed,
+            // nothing we could do about that
+            let _ = func.apply(&navigator, &args);
+        }
+    }
+}
+
+pub(crate) fn clear_badge() {
+    set_badge(0);
+}
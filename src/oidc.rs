@@ -0,0 +1,144 @@
+use anyhow::{format_err, Error};
+use gloo_utils::window;
+use proxmox_login::TicketResult;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use wasm_bindgen::JsValue;
+
+use proxmox_yew_comp::{http_get, http_login, http_post};
+
+// Logging in with an OpenID Connect realm leaves the app for the provider,
+// which redirects back with a code the server exchanges for a ticket. What is
+// needed to finish the login is kept in the session storage meanwhile.
+const PENDING_KEY: &str = "pmg-quarantine-oidc-login";
+
+#[derive(Serialize, Deserialize)]
+struct PendingLogin {
+    redirect_url: String,
+    // query string of the page the login started on, e.g. of a report link
+    search: String,
+}
+
+/// Authentication realm as listed by the server.
+#[derive(Clone, PartialEq, Deserialize)]
+pub(crate) struct Realm {
+    pub realm: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+impl Realm {
+    pub fn is_openid(&self) -> bool {
+        matches!(self.ty.as_str(), "oidc" | "openid")
+    }
+
+    pub fn label(&self) -> &str {
+        self.comment
+            .as_deref()
+            .filter(|comment| !comment.is_empty())
+            .unwrap_or(&self.realm)
+    }
+}
+
+pub(crate) async fn load_realms() -> Result<Vec<Realm>, Error> {
+    http_get("/access/auth-realm", None).await
+}
+
+#[derive(Deserialize)]
+struct OpenIdTicket {
+    username: String,
+    ticket: String,
+}
+
+/// Leaves for the login page of the provider of an OpenID Connect realm.
+pub(crate) async fn start_openid_login(realm: &str) -> Result<(), Error> {
+    let location = window().location();
+    let js_err = |err| format_err!("could not read location: {err:?}");
+    // the provider returns to the page serving this app
+    let redirect_url = format!(
+        "{}{}",
+        location.origin().map_err(js_err)?,
+        location.pathname().map_err(js_err)?
+    );
+
+    let url: String = http_post(
+        "/access/oidc/auth-url",
+        Some(json!({ "realm": realm, "redirect-url": redirect_url })),
+    )
+    .await?;
+
+    let pending = PendingLogin {
+        redirect_url,
+        search: location.search().unwrap_or_default(),
+    };
+    let storage = window()
+        .session_storage()
+        .ok()
+        .flatten()
+        .ok_or_else(|| format_err!("session storage is not available"))?;
+    storage
+        .set_item(PENDING_KEY, &serde_json::to_string(&pending)?)
+        .map_err(|err| format_err!("could not store login state: {err:?}"))?;
+
+    location
+        .set_href(&url)
+        .map_err(|err| format_err!("could not open login page: {err:?}"))
+}
+
+/// Whether the app was loaded by the redirect back from a provider.
+pub(crate) fn openid_login_pending() -> bool {
+    window()
+        .session_storage()
+        .ok()
+        .flatten()
+        .and_then(|storage| storage.get_item(PENDING_KEY).ok().flatten())
+        .is_some()
+}
+
+// Takes the state of the login in progress, and drops the parameters the
+// provider redirected back with from the location.
+fn take_pending_login() -> Option<PendingLogin> {
+    let storage = window().session_storage().ok().flatten()?;
+    let pending = storage.get_item(PENDING_KEY).ok().flatten();
+    let _ = storage.remove_item(PENDING_KEY);
+    let pending: PendingLogin = serde_json::from_str(&pending?).ok()?;
+
+    let url = format!("{}{}", pending.redirect_url, pending.search);
+    let _ = window()
+        .history()
+        .and_then(|history| history.replace_state_with_url(&JsValue::NULL, "", Some(&url)));
+    Some(pending)
+}
+
+/// Completes the login with the parameters the provider redirected back
+/// with, restoring the query string of the page the login started on.
+pub(crate) async fn finish_openid_login(
+    code: String,
+    state: String,
+) -> Result<TicketResult, Error> {
+    let pending = take_pending_login().ok_or_else(|| format_err!("no login in progress"))?;
+
+    let result: OpenIdTicket = http_post(
+        "/access/oidc/login",
+        Some(json!({
+            "code": code,
+            "state": state,
+            "redirect-url": pending.redirect_url,
+        })),
+    )
+    .await?;
+
+    // the new ticket serves as password to set up the session as usual
+    let (user, realm) = result
+        .username
+        .rsplit_once('@')
+        .ok_or_else(|| format_err!("invalid user name '{}'", result.username))?;
+    Ok(http_login(user, result.ticket, realm).await?)
+}
+
+/// Drops an unfinished login, e.g. when the provider reported an error.
+pub(crate) fn cancel_openid_login() {
+    take_pending_login();
+}
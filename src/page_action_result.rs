@@ -0,0 +1,430 @@
+use std::rc::Rc;
+
+use anyhow::Error;
+use gloo_utils::window;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use yew::virtual_dom::{VComp, VNode};
+use yew_router::scope_ext::RouterScopeExt;
+
+use pwt::css::{AlignItems, ColorScheme, FlexFit, JustifyContent, Opacity};
+use pwt::prelude::*;
+use pwt::touch::{ApplicationBar, Scaffold};
+use pwt::widget::{error_message, Button, Column, Container, Fa, Progress, Row};
+
+use crate::a11y::announce;
+use crate::date_format::{format_date, format_time};
+use crate::sender_lists::{add_to_sender_list, remove_from_sender_list, SenderList};
+use crate::session::{http_get, is_not_found};
+use crate::spam_list::sender_address;
+use crate::{mail_action, MailAction, QuarantineReload, Route};
+
+/// Result page for an action requested by a link in a spam report.
+///
+/// Shows which mail was affected and whether the action worked, and offers
+/// the usual next steps.
+#[derive(Clone, PartialEq, Properties)]
+pub struct PageActionResult {
+    id: String,
+    action: MailAction,
+}
+
+impl PageActionResult {
+    pub fn new(id: impl Into<String>, action: MailAction) -> Self {
+        yew::props!(Self {
+            id: id.into(),
+            action
+        })
+    }
+}
+
+// what the report needs to know about the mail, loaded before the action as
+// it may be gone afterwards
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct MailSummary {
+    #[serde(default)]
+    from: String,
+    #[serde(default)]
+    subject: String,
+    #[serde(default)]
+    time: i64,
+}
+
+// Actions that already ran in this tab are kept in the session storage with
+// the summary of their mail, so reloading the page shows their result again
+// instead of repeating them.
+const DONE_KEY_PREFIX: &str = "pmg-quarantine-action-";
+
+fn done_key(action: MailAction, id: &str) -> String {
+    format!("{DONE_KEY_PREFIX}{action}-{id}")
+}
+
+fn load_done(action: MailAction, id: &str) -> Option<MailSummary> {
+    let storage = window().session_storage().ok().flatten()?;
+    let summary = storage.get_item(&done_key(action, id)).ok().flatten()?;
+    serde_json::from_str(&summary).ok()
+}
+
+fn store_done(action: MailAction, id: &str, summary: &MailSummary) {
+    let Ok(Some(storage)) = window().session_storage() else {
+        return;
+    };
+    match serde_json::to_string(summary) {
+        Ok(summary) => {
+            if let Err(err) = storage.set_item(&done_key(action, id), &summary) {
+                log::error!("could not store action result: {err:?}");
+            }
+        }
+        Err(err) => log::error!("could not serialize mail summary: {err}"),
+    }
+}
+
+/// Follow-up changes to the sender lists.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Followup {
+    /// Take the sender off the blocklist and welcomelist it instead.
+    UndoBlocklist,
+    Welcomelist,
+    Blocklist,
+}
+
+impl Followup {
+    fn for_action(action: MailAction) -> Option<Self> {
+        match action {
+            MailAction::Blocklist => Some(Followup::UndoBlocklist),
+            MailAction::Deliver => Some(Followup::Welcomelist),
+            MailAction::Delete => Some(Followup::Blocklist),
+            _ => None,
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            Followup::UndoBlocklist => tr!("Undo via Welcomelist"),
+            Followup::Welcomelist => tr!("Welcomelist this Sender too"),
+            Followup::Blocklist => tr!("Blocklist this Sender too"),
+        }
+    }
+
+    fn icon_class(&self) -> &'static str {
+        match self {
+            Followup::UndoBlocklist => "fa fa-undo",
+            Followup::Welcomelist => MailAction::Welcomelist.icon_class(),
+            Followup::Blocklist => MailAction::Blocklist.icon_class(),
+        }
+    }
+
+    async fn run(&self, address: &str) -> Result<String, Error> {
+        match self {
+            Followup::UndoBlocklist => {
+                remove_from_sender_list(SenderList::Blocklist, address).await?;
+                add_to_sender_list(SenderList::Welcomelist, address).await?;
+                Ok(tr!(
+                    "Removed {0} from the blocklist and added it to the welcomelist",
+                    address
+                ))
+            }
+            Followup::Welcomelist => {
+                add_to_sender_list(SenderList::Welcomelist, address).await?;
+                Ok(tr!(
+                    "Added {0} to {1}",
+                    address,
+                    SenderList::Welcomelist.label()
+                ))
+            }
+            Followup::Blocklist => {
+                add_to_sender_list(SenderList::Blocklist, address).await?;
+                Ok(tr!(
+                    "Added {0} to {1}",
+                    address,
+                    SenderList::Blocklist.label()
+                ))
+            }
+        }
+    }
+}
+
+pub enum Msg {
+    Summary(Result<MailSummary, Error>),
+    ActionResult(Result<(), Error>),
+    Followup(Followup),
+    FollowupResult(Followup, Result<String, Error>),
+    Retry,
+    ShowQuarantine,
+}
+
+pub struct PmgPageActionResult {
+    summary: Option<Result<MailSummary, Error>>,
+    result: Option<Result<(), Error>>,
+    followup_running: bool,
+    // outcome of the last follow-up
+    followup: Option<Result<String, Error>>,
+    // follow-ups already done, offered only once
+    done: Vec<Followup>,
+}
+
+impl PmgPageActionResult {
+    fn outcome_message(&self, action: MailAction) -> Option<(bool, String)> {
+        let message = match (&self.summary, &self.result) {
+            (Some(Err(err)), _) if is_not_found(err) => {
+                (false, tr!("The mail is no longer in the quarantine"))
+            }
+            (Some(Err(_)), _) => (false, tr!("Could not load the mail")),
+            (_, Some(Ok(()))) => (true, tr!("Action '{0}' successful", action)),
+            (_, Some(Err(_))) => (false, tr!("Action '{0}' failed", action)),
+            _ => return None,
+        };
+        Some(message)
+    }
+
+    // Loads the summary, the action follows if the mail is still there.
+    fn load_summary(ctx: &Context<Self>) {
+        let link = ctx.link().clone();
+        let id = ctx.props().id.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let summary = http_get("/quarantine/content", Some(json!({ "id": id }))).await;
+            link.send_message(Msg::Summary(summary));
+        });
+    }
+
+    fn mail_details(summary: &MailSummary) -> Html {
+        let line = |label: String, value: String| {
+            Row::new()
+                .gap(2)
+                .with_child(
+                    Container::new()
+                        .class(Opacity::Half)
+                        .style("min-width", "5em")
+                        .with_child(label),
+                )
+                .with_child(
+                    Container::new()
+                        .class(FlexFit)
+                        .style("overflow-wrap", "anywhere")
+                        .with_child(value),
+                )
+        };
+        let mut details = Column::new()
+            .gap(1)
+            .padding(2)
+            .class("pwt-border-radius-medium")
+            .class(ColorScheme::SurfaceContainer)
+            .with_child(line(tr!("From"), summary.from.clone()))
+            .with_child(line(tr!("Subject"), summary.subject.clone()));
+        if summary.time > 0 {
+            details.add_child(line(
+                tr!("Received"),
+                format!(
+                    "{} {}",
+                    format_date(summary.time),
+                    format_time(summary.time)
+                ),
+            ));
+        }
+        details.into()
+    }
+}
+
+impl Component for PmgPageActionResult {
+    type Message = Msg;
+    type Properties = PageActionResult;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let props = ctx.props();
+        let (summary, result) = match load_done(props.action, &props.id) {
+            Some(summary) => (Some(Ok(summary)), Some(Ok(()))),
+            None => {
+                Self::load_summary(ctx);
+                (None, None)
+            }
+        };
+
+        Self {
+            summary,
+            result,
+            followup_running: false,
+            followup: None,
+            done: Vec::new(),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let props = ctx.props();
+        match msg {
+            Msg::Summary(summary) => {
+                // only act on mails that are still there
+                if summary.is_ok() {
+                    let link = ctx.link().clone();
+                    let (id, action) = (props.id.clone(), props.action);
+                    wasm_bindgen_futures::spawn_local(async move {
+                        let result = mail_action(&id, action).await.map(|_| ());
+                        link.send_message(Msg::ActionResult(result));
+                    });
+                }
+                self.summary = Some(summary);
+                if let Some((_, message)) = self.outcome_message(props.action) {
+                    announce(&message);
+                }
+            }
+            Msg::ActionResult(result) => {
+                if result.is_ok() {
+                    if let Some(Ok(summary)) = &self.summary {
+                        store_done(props.action, &props.id, summary);
+                    }
+                    if let Some((reload, _handle)) =
+                        ctx.link().context::<QuarantineReload>(Callback::noop())
+                    {
+                        reload.bump();
+                    }
+                }
+                self.result = Some(result);
+                if let Some((_, message)) = self.outcome_message(props.action) {
+                    announce(&message);
+                }
+            }
+            Msg::Followup(followup) => {
+                let Some(Ok(summary)) = &self.summary else {
+                    return false;
+                };
+                let address = sender_address(&summary.from);
+                let link = ctx.link().clone();
+                self.followup_running = true;
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = followup.run(&address).await;
+                    link.send_message(Msg::FollowupResult(followup, result));
+                });
+            }
+            Msg::FollowupResult(followup, result) => {
+                self.followup_running = false;
+                match &result {
+                    Ok(message) => {
+                        announce(message);
+                        self.done.push(followup);
+                    }
+                    Err(err) => announce(&err.to_string()),
+                }
+                self.followup = Some(result);
+            }
+            Msg::Retry => {
+                self.summary = None;
+                self.result = None;
+                Self::load_summary(ctx);
+            }
+            Msg::ShowQuarantine => {
+                // the list replaces this page, going back must not repeat the action
+                if let Some(navigator) = ctx.link().navigator() {
+                    navigator.replace(&Route::SpamList);
+                }
+                return false;
+            }
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+        let mut body = Column::new()
+            .class(FlexFit)
+            .padding(2)
+            .gap(2)
+            .attribute("aria-live", "polite");
+
+        match self.outcome_message(props.action) {
+            Some((success, message)) => {
+                let (icon, color) = if success {
+                    ("check-circle", "var(--pwt-color-primary)")
+                } else {
+                    ("exclamation-triangle", "var(--pwt-color-error)")
+                };
+                body.add_child(
+                    Row::new()
+                        .class(AlignItems::Center)
+                        .gap(2)
+                        .with_child(
+                            Container::new()
+                                .style("color", color)
+                                .with_child(Fa::new(icon).large_2x()),
+                        )
+                        .with_child(
+                            Container::new()
+                                .class("pwt-font-title-large")
+                                .with_child(message),
+                        ),
+                );
+            }
+            None => body.add_child(Progress::new()),
+        }
+
+        let mut retry = false;
+        match (&self.summary, &self.result) {
+            (Some(Err(err)), _) => {
+                if is_not_found(err) {
+                    body.add_child(tr!(
+                        "It may have been delivered or deleted already, e.g. via another link."
+                    ));
+                } else {
+                    retry = true;
+                }
+                body.add_child(error_message(&err.to_string()));
+            }
+            (_, Some(Err(err))) => {
+                retry = true;
+                body.add_child(error_message(&err.to_string()));
+            }
+            _ => {}
+        }
+
+        if let Some(Ok(summary)) = &self.summary {
+            body.add_child(Self::mail_details(summary));
+        }
+
+        let mut buttons = Column::new().gap(1).class(JustifyContent::Stretch);
+        if retry {
+            buttons.add_child(
+                Button::new(tr!("Retry"))
+                    .icon_class("fa fa-refresh")
+                    .class("pwt-button-outline")
+                    .on_activate(ctx.link().callback(|_| Msg::Retry)),
+            );
+        }
+        if matches!(self.result, Some(Ok(()))) {
+            if let Some(followup) = Followup::for_action(props.action) {
+                if !self.done.contains(&followup) {
+                    buttons.add_child(
+                        Button::new(followup.label())
+                            .icon_class(followup.icon_class())
+                            .class("pwt-button-outline")
+                            .disabled(self.followup_running)
+                            .on_activate(ctx.link().callback(move |_| Msg::Followup(followup))),
+                    );
+                }
+            }
+        }
+        buttons.add_child(
+            Button::new(tr!("View Quarantine"))
+                .icon_class("fa fa-list")
+                .class("pwt-scheme-primary")
+                .on_activate(ctx.link().callback(|_| Msg::ShowQuarantine)),
+        );
+
+        body.add_optional_child(match &self.followup {
+            Some(Ok(message)) => Some(Html::from(message.clone())),
+            Some(Err(err)) => Some(error_message(&err.to_string()).into()),
+            None => None,
+        });
+        body.add_child(buttons);
+
+        Scaffold::new()
+            .application_bar(ApplicationBar::new().title(tr!("Quarantine Action")))
+            .body(body)
+            .into()
+    }
+}
+
+impl From<PageActionResult> for VNode {
+    fn from(val: PageActionResult) -> Self {
+        let comp = VComp::new::<PmgPageActionResult>(Rc::new(val), None);
+        VNode::from(comp)
+    }
+}
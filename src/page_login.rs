@@ -1,26 +1,37 @@
 use std::rc::Rc;
 
+use anyhow::Error;
 use gloo_utils::document;
 use percent_encoding::percent_decode_str;
 use proxmox_login::{Authentication, TicketResult};
 use yew::{
-    html::IntoEventCallback,
+    html,
+    html::{IntoEventCallback, TargetCast},
     virtual_dom::{VComp, VNode},
     Callback, Component, Properties,
 };
 
+use web_sys::{Event, HtmlSelectElement, UrlSearchParams};
+use yew_router::history::{BrowserHistory, History};
+
 use pwt::{
     css::{AlignItems, JustifyContent},
     props::{ContainerBuilder, CssPaddingBuilder, WidgetBuilder, WidgetStyleBuilder},
     touch::{SnackBar, SnackBarContextExt},
-    widget::{Column, Container, Image, Row},
+    tr,
+    widget::{get_unique_element_id, Button, Column, Container, Image, Progress, Row},
 };
 
 use proxmox_yew_comp::{
     http_login, start_ticket_refresh_loop, stop_ticket_refresh_loop, LoginPanel,
 };
 
+use crate::oidc::{
+    cancel_openid_login, finish_openid_login, load_realms, openid_login_pending,
+    start_openid_login, Realm,
+};
 use crate::spam_list::extract_query_parameter;
+use crate::ServerConfig;
 
 #[derive(Properties, PartialEq)]
 pub struct PageLogin {
@@ -31,6 +42,10 @@ pub struct PageLogin {
 pub enum Msg {
     Login(Authentication),
     LoginError(proxmox_client::Error),
+    Realms(Result<Vec<Realm>, Error>),
+    SelectRealm(String),
+    OpenIdLogin(String), // realm
+    OpenIdError(Error),
 }
 
 impl PageLogin {
@@ -50,7 +65,41 @@ impl Default for PageLogin {
     }
 }
 
-pub struct PmgPageLogin {}
+/// Spam report links point to `/quarantine`, while the routes of the app
+/// start at the root. Moves there, keeping all parameters of the link but the
+/// ticket, so the list can act on them.
+pub(crate) fn leave_quarantine_path() {
+    let location = document().location().unwrap();
+    if location.pathname().ok().as_deref() != Some("/quarantine") {
+        return;
+    }
+    let search = location.search().unwrap_or_default();
+    let Ok(param) = UrlSearchParams::new_with_str(&search) else {
+        return;
+    };
+    param.delete("ticket");
+    let query: String = param.to_string().into();
+    let path = if query.is_empty() {
+        String::from("/")
+    } else {
+        format!("/?{query}")
+    };
+    BrowserHistory::new().replace(path);
+}
+
+// realm preselected for password logins, the one of quarantine users
+const DEFAULT_REALM: &str = "quarantine";
+
+pub struct PmgPageLogin {
+    // realms to log in with a password, offered in our own selector, as the
+    // one of the login panel would list the OpenID Connect realms as well
+    password_realms: Vec<Realm>,
+    realm: String,
+    // OpenID Connect realms, offered as single sign-on buttons
+    openid_realms: Vec<Realm>,
+    // set while completing the login after the redirect back from a provider
+    finishing: bool,
+}
 
 impl PmgPageLogin {
     fn ticket_login(ctx: &yew::Context<Self>, username: String, ticket: String) {
@@ -71,6 +120,48 @@ impl PmgPageLogin {
             }
         });
     }
+
+    // Completes a login with an OpenID Connect realm after the provider
+    // redirected back, returns whether one was in progress.
+    fn openid_callback(ctx: &yew::Context<Self>) -> bool {
+        if !openid_login_pending() {
+            return false;
+        }
+        let param = |name| extract_query_parameter(name).ok().flatten();
+        if let Some(error) = param("error") {
+            cancel_openid_login();
+            let error = percent_decode_str(&error).decode_utf8_lossy().to_string();
+            ctx.link()
+                .send_message(Msg::OpenIdError(anyhow::format_err!(
+                    "{}: {error}",
+                    tr!("Login failed")
+                )));
+            return false;
+        }
+        let (Some(code), Some(state)) = (param("code"), param("state")) else {
+            cancel_openid_login();
+            return false;
+        };
+        let code = percent_decode_str(&code).decode_utf8_lossy().to_string();
+        let state = percent_decode_str(&state).decode_utf8_lossy().to_string();
+
+        let link = ctx.link().clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            match finish_openid_login(code, state).await {
+                Ok(TicketResult::Full(info) | TicketResult::HttpOnly(info)) => {
+                    start_ticket_refresh_loop();
+                    link.send_message(Msg::Login(info));
+                }
+                Ok(TicketResult::TfaRequired(_)) => {
+                    link.send_message(Msg::OpenIdError(anyhow::format_err!(
+                        "TFA required, but not implemented"
+                    )));
+                }
+                Err(err) => link.send_message(Msg::OpenIdError(err)),
+            }
+        });
+        true
+    }
 }
 
 impl Component for PmgPageLogin {
@@ -100,13 +191,26 @@ impl Component for PmgPageLogin {
             }
         }
 
-        Self {}
+        let finishing = Self::openid_callback(ctx);
+
+        let link = ctx.link().clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            link.send_message(Msg::Realms(load_realms().await));
+        });
+
+        Self {
+            password_realms: Vec::new(),
+            realm: DEFAULT_REALM.to_string(),
+            openid_realms: Vec::new(),
+            finishing,
+        }
     }
 
     fn update(&mut self, ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
         let props = ctx.props();
         match msg {
             Msg::Login(authentication) => {
+                leave_quarantine_path();
                 if let Some(cb) = &props.on_login {
                     cb.emit(authentication);
                 }
@@ -115,37 +219,133 @@ impl Component for PmgPageLogin {
                 ctx.link()
                     .show_snackbar(SnackBar::new().message(error.to_string()));
             }
+            Msg::Realms(Ok(realms)) => {
+                let (openid, password): (Vec<_>, Vec<_>) =
+                    realms.into_iter().partition(Realm::is_openid);
+                if !password.iter().any(|realm| realm.realm == self.realm) {
+                    if let Some(first) = password.first() {
+                        self.realm = first.realm.clone();
+                    }
+                }
+                self.password_realms = password;
+                self.openid_realms = openid;
+            }
+            Msg::SelectRealm(realm) => self.realm = realm,
+            Msg::Realms(Err(err)) => {
+                log::error!("could not load authentication realms: {err}");
+                return false;
+            }
+            Msg::OpenIdLogin(realm) => {
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    // leaves the app on success
+                    if let Err(err) = start_openid_login(&realm).await {
+                        link.send_message(Msg::OpenIdError(err));
+                    }
+                });
+                return false;
+            }
+            Msg::OpenIdError(error) => {
+                self.finishing = false;
+                ctx.link()
+                    .show_snackbar(SnackBar::new().message(error.to_string()));
+            }
         }
         true
     }
 
     fn view(&self, ctx: &yew::Context<Self>) -> yew::Html {
-        Column::new()
-            .with_child(
-                Row::new()
+        let config = ctx
+            .link()
+            .context::<ServerConfig>(Callback::noop())
+            .map(|(config, _)| config)
+            .unwrap_or_default();
+        let mut column = Column::new().with_child(
+            Row::new()
+                .padding(2)
+                .gap(1)
+                .class(AlignItems::Center)
+                .class(JustifyContent::Center)
+                .with_child(
+                    Image::new(config.image_url("proxmox_logo.svg"))
+                        .dark_mode_src(config.image_url("proxmox_logo_white.svg"))
+                        .height(30),
+                )
+                .with_child(
+                    Container::new()
+                        .class("pwt-font-headline-small")
+                        .with_child("Mail Gateway"),
+                ),
+        );
+
+        if self.finishing {
+            column.add_child(
+                Column::new()
                     .padding(2)
-                    .gap(1)
+                    .gap(2)
+                    .class(AlignItems::Center)
+                    .with_child(tr!("Logging in..."))
+                    .with_child(Progress::new()),
+            );
+            return column.into();
+        }
+
+        if self.password_realms.len() > 1 {
+            let id = get_unique_element_id();
+            column.add_child(
+                Row::new()
+                    .padding_x(2)
+                    .gap(2)
                     .class(AlignItems::Center)
-                    .class(JustifyContent::Center)
-                    .with_child(
-                        Image::new("/mobile/images/proxmox_logo.svg")
-                            .dark_mode_src("/mobile/images/proxmox_logo_white.svg")
-                            .height(30),
-                    )
-                    .with_child(
-                        Container::new()
-                            .class("pwt-font-headline-small")
-                            .with_child("Mail Gateway"),
-                    ),
-            )
-            .with_child(
+                    .with_child(html! { <label for={id.clone()}>{tr!("Realm")}</label> })
+                    .with_child(html! {
+                        <select
+                            {id}
+                            class="pwt-input pwt-flex-fill"
+                            onchange={ctx.link().callback(|event: Event| {
+                                Msg::SelectRealm(event.target_unchecked_into::<HtmlSelectElement>().value())
+                            })}
+                        >
+                            { for self.password_realms.iter().map(|realm| html! {
+                                <option value={realm.realm.clone()} selected={realm.realm == self.realm}>
+                                    {realm.label()}
+                                </option>
+                            })}
+                        </select>
+                    }),
+            );
+        }
+
+        // recreated for another realm, the panel takes it on creation only
+        column.add_child(
+            Container::new().key(self.realm.clone()).with_child(
                 LoginPanel::new()
                     .mobile(true)
                     .realm_selectable(false)
+                    .default_realm(self.realm.clone())
                     .domain_path("/access/auth-realm")
                     .on_login(ctx.link().callback(Msg::Login)),
-            )
-            .into()
+            ),
+        );
+
+        if !self.openid_realms.is_empty() {
+            let mut buttons = Column::new()
+                .padding_x(2)
+                .gap(1)
+                .class(JustifyContent::Stretch);
+            for realm in &self.openid_realms {
+                let name = realm.realm.clone();
+                buttons.add_child(
+                    Button::new(tr!("Log in with {0}", realm.label()))
+                        .icon_class("fa fa-openid")
+                        .class("pwt-button-outline")
+                        .on_activate(ctx.link().callback(move |_| Msg::OpenIdLogin(name.clone()))),
+                );
+            }
+            column.add_child(buttons);
+        }
+
+        column.into()
     }
 }
 
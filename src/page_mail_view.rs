@@ -1,99 +1,232 @@
 use std::rc::Rc;
 
 use anyhow::Error;
-use serde_json::{json, Value};
+use serde_json::Value;
 
+use web_sys::{HtmlIFrameElement, KeyboardEvent};
+use yew::html::Scope;
 use yew::virtual_dom::{VComp, VNode};
+use yew_router::scope_ext::RouterScopeExt;
 
-use proxmox_yew_comp::http_get;
 use pwt::dom::get_system_prefer_dark_mode;
 use pwt::prelude::*;
-use pwt::state::{SharedState, Theme, ThemeObserver};
-use pwt::touch::{ApplicationBar, FabMenu, FabMenuEntry, Scaffold, SnackBar, SnackBarContextExt};
+use pwt::state::{Theme, ThemeObserver};
+use pwt::touch::{ApplicationBar, Fab, Scaffold, SnackBar, SnackBarContextExt};
 use pwt::widget::form::Checkbox;
-use pwt::widget::{get_unique_element_id, FieldLabel, Row};
-
-use crate::{mail_action, MailAction, QuarantineReload};
-
-// whether the mail has external images the on-demand mode blocks, so the
-// "Load images" toggle is only offered when it would actually fetch something
-async fn mail_has_external_images(id: &str) -> bool {
-    match http_get::<Value>("/quarantine/content", Some(json!({ "id": id }))).await {
-        // a boolean can arrive as a JSON bool, number or one of the strings the
-        // PVE::JSONSchema boolean type accepts (1/on/yes/true vs 0/off/no/false)
-        Ok(data) => match &data["external_images"] {
-            Value::Bool(b) => *b,
-            Value::Number(n) => n.as_i64().unwrap_or(0) != 0,
-            Value::String(s) => {
-                matches!(s.to_ascii_lowercase().as_str(), "1" | "on" | "yes" | "true")
-            }
-            _ => false,
-        },
-        Err(_) => false,
+use pwt::widget::{error_message, get_unique_element_id, Button, FieldLabel, Progress, Row};
+
+use crate::a11y::announce;
+use crate::action_sheet::{batch_confirm_dialog, preview_action_sheet};
+use crate::history::{sync_history_guard, HistoryGuard};
+use crate::keyboard::{shortcut_help, KeyListener, Shortcut};
+use crate::link_sheet::LinkSheet;
+use crate::list_dialog::{retroactive_snackbar, AddedEntry, ListDialog};
+use crate::mail_content::{
+    activated_link, load_mail_content, sandboxed_document, MailContent, MailLink, SandboxedDocument,
+};
+use crate::sender_lists::{matching_mails, SenderList};
+use crate::sender_sheet::SenderSheet;
+use crate::{mail_action, mail_action_batch, LoadedMails, MailAction, QuarantineReload};
+
+#[derive(Default)]
+pub struct MailFlags {
+    // whether the mail has external images the on-demand mode blocks, so the
+    // "Load images" toggle is only offered when it would actually fetch something
+    external_images: bool,
+    // initial state for toggling seen via keyboard
+    seen: bool,
+    // the From header, for the sender sheet
+    from: String,
+}
+
+impl From<&Value> for MailFlags {
+    fn from(data: &Value) -> Self {
+        Self {
+            external_images: parse_boolean(&data["external_images"]),
+            seen: parse_boolean(&data["seen"]),
+            from: data["from"].as_str().unwrap_or_default().to_string(),
+        }
+    }
+}
+
+// a boolean can arrive as a JSON bool, number or one of the strings the
+// PVE::JSONSchema boolean type accepts (1/on/yes/true vs 0/off/no/false)
+fn parse_boolean(value: &Value) -> bool {
+    match value {
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_i64().unwrap_or(0) != 0,
+        Value::String(s) => {
+            matches!(s.to_ascii_lowercase().as_str(), "1" | "on" | "yes" | "true")
+        }
+        _ => false,
     }
 }
 
 #[derive(Clone, PartialEq, Properties)]
 pub struct PageMailView {
     id: String,
+    /// Handle keyboard shortcuts, off while something else on the page has
+    /// the keyboard.
+    #[prop_or_default]
+    keyboard: bool,
 }
 
 impl PageMailView {
     pub fn new(id: impl Into<String>) -> Self {
         yew::props!(Self { id: id.into() })
     }
+
+    pub fn keyboard(mut self, keyboard: bool) -> Self {
+        self.keyboard = keyboard;
+        self
+    }
 }
 
 pub enum Msg {
     ActionResult(MailAction, Result<Value, Error>),
-    DarkmodeFilter(bool), // on/off
-    DarkmodeChange(bool), // on/off
-    LoadImages(bool),     // on/off
-    ExternalImages(bool), // mail has external images to load
+    DarkmodeFilter(bool),                              // on/off
+    DarkmodeChange(bool),                              // on/off
+    LoadImages(bool),                                  // on/off
+    Content(String, bool, Result<MailContent, Error>), // id, with images
+    Key(KeyboardEvent),
+    CloseShortcuts,
+    ShowSender(bool), // on/off
+    Action(MailAction),
+    CloseListDialog,
+    EntryAdded(AddedEntry),
+    ConfirmBatch(Vec<String>, MailAction), // ids
+    CancelBatch,
+    BatchAction(Vec<String>, MailAction), // ids
+    ShowActions(bool),                    // on/off
+    CloseLink,
+    FrameLoaded,
+    Back,
 }
 pub struct PmgPageMailView {
     show_dark_mode_filter: bool,
     dark_mode_filter: bool,
     load_images: bool,
     show_load_images: bool,
+    // HTML of the mail, fetched with the session instead of by the iframe
+    // and prepared for it
+    content: Option<Result<SandboxedDocument, String>>,
+    frame: NodeRef,
+    // documents loaded by the iframe, more than one means it navigated
+    frame_loads: usize,
+    // recreates the iframe after it navigated away from the mail
+    frame_generation: usize,
+    // link the mail tried to open, shown for confirmation
+    link: Option<MailLink>,
+    seen: bool,
+    from: String,
+    show_shortcuts: bool,
+    show_sender: bool,
+    list_dialog: Option<SenderList>,
+    show_actions: bool,
+    // action for other mails of the sender waiting for confirmation
+    pending_batch: Option<(Vec<String>, MailAction)>,
+    // history entry while an overlay is open
+    history_guard: Option<HistoryGuard>,
     _theme_observer: ThemeObserver,
-    reload: Option<SharedState<usize>>,
+    _key_listener: KeyListener,
+    reload: Option<QuarantineReload>,
 }
 
 impl PmgPageMailView {
-    fn action_callback(&self, ctx: &Context<Self>, action: MailAction) -> Callback<MouseEvent> {
-        let props = ctx.props();
-
+    fn load_content(&mut self, ctx: &Context<Self>) {
+        self.content = None;
+        self.frame_loads = 0;
         let link = ctx.link().clone();
-        let id = props.id.clone();
-
-        Callback::from(move |_event: MouseEvent| {
-            let link = link.clone();
-            let id = id.clone();
-            wasm_bindgen_futures::spawn_local(async move {
-                let result = mail_action(&id, action).await;
-                link.send_message(Msg::ActionResult(action, result));
-            });
-        })
+        let id = ctx.props().id.clone();
+        let load_images = self.load_images;
+        wasm_bindgen_futures::spawn_local(async move {
+            let result = load_mail_content(&id, load_images).await;
+            link.send_message(Msg::Content(id, load_images, result));
+        });
+    }
+
+    fn run_action(link: &Scope<Self>, id: String, action: MailAction) {
+        let link = link.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let result = mail_action(&id, action).await;
+            link.send_message(Msg::ActionResult(action, result));
+        });
+    }
+
+    // dialogs and sheets on top of the mail, which take the keyboard
+    fn overlay_open(&self) -> bool {
+        self.list_dialog.is_some()
+            || self.pending_batch.is_some()
+            || self.show_sender
+            || self.show_actions
+            || self.link.is_some()
+            || self.show_shortcuts
+    }
+
+    // returns whether the shortcut was handled
+    fn handle_shortcut(&mut self, ctx: &Context<Self>, shortcut: Shortcut) -> bool {
+        // the dialogs close by themselves
+        if !ctx.props().keyboard || self.list_dialog.is_some() || self.pending_batch.is_some() {
+            return false;
+        }
+        // the sheets and the help only close
+        if self.overlay_open() && shortcut != Shortcut::Back {
+            return false;
+        }
+        match shortcut {
+            Shortcut::Back => {
+                if self.show_shortcuts {
+                    self.show_shortcuts = false;
+                } else if self.show_sender {
+                    self.show_sender = false;
+                } else if self.show_actions {
+                    self.show_actions = false;
+                } else if self.link.is_some() {
+                    self.link = None;
+                } else if let Some(navigator) = ctx.link().navigator() {
+                    navigator.back();
+                }
+            }
+            Shortcut::Action(action) => ctx.link().send_message(Msg::Action(action)),
+            Shortcut::ToggleSeen => {
+                let action = if self.seen {
+                    MailAction::MarkUnseen
+                } else {
+                    MailAction::MarkSeen
+                };
+                Self::run_action(ctx.link(), ctx.props().id.clone(), action);
+            }
+            Shortcut::Help => self.show_shortcuts = true,
+            // list navigation stays with the list
+            Shortcut::Next | Shortcut::Previous | Shortcut::Open => return false,
+        }
+        true
     }
 
     fn content_view(&self, ctx: &Context<Self>) -> Html {
         let props = ctx.props();
 
+        let srcdoc = match &self.content {
+            None => return Progress::new().into(),
+            Some(Err(err)) => return error_message(err).into(),
+            Some(Ok(document)) => document.srcdoc.clone(),
+        };
+
         let style = self
             .dark_mode_filter
             .then_some("filter: brightness(95%) invert(95%);");
 
-        let mut src = format!("/api2/htmlmail/quarantine/content?id={}", props.id);
-        if self.load_images {
-            src.push_str("&images=1");
-        }
-
-        // key on src so toggling images recreates the iframe instead of
+        // The mail does not get the origin of the app, so it cannot use the
+        // session, and cannot run scripts.
+        // key on the shown mail so a new one recreates the iframe instead of
         // navigating it, which would otherwise add a browser-history entry
-        let src_key = src.clone();
+        let key = format!(
+            "{}-{}-{}",
+            props.id, self.load_images, self.frame_generation
+        );
+        let onload = ctx.link().callback(|_| Msg::FrameLoaded);
         html! {
-            <iframe key={src_key} {style} frameborder="0" width="100%" height="100%" sandbox="allow-same-origin" {src}>
+            <iframe {key} ref={self.frame.clone()} {style} {onload} frameborder="0" width="100%" height="100%" sandbox="" {srcdoc}>
             </iframe>
         }
     }
@@ -119,22 +252,32 @@ impl Component for PmgPageMailView {
         let reload = ctx
             .link()
             .context::<QuarantineReload>(Callback::noop())
-            .map(|(reload, _handle)| reload.0);
-
-        let link = ctx.link().clone();
-        let id = ctx.props().id.clone();
-        wasm_bindgen_futures::spawn_local(async move {
-            link.send_message(Msg::ExternalImages(mail_has_external_images(&id).await));
-        });
+            .map(|(reload, _handle)| reload);
 
-        Self {
+        let mut me = Self {
             dark_mode_filter,
             show_dark_mode_filter: dark_mode_filter,
             load_images: false,
             show_load_images: false,
+            content: None,
+            frame: NodeRef::default(),
+            frame_loads: 0,
+            frame_generation: 0,
+            link: None,
+            seen: false,
+            from: String::new(),
+            show_shortcuts: false,
+            show_sender: false,
+            list_dialog: None,
+            show_actions: false,
+            pending_batch: None,
+            history_guard: None,
             _theme_observer,
+            _key_listener: KeyListener::new(ctx.link().callback(Msg::Key)),
             reload,
-        }
+        };
+        me.load_content(ctx);
+        me
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
@@ -144,12 +287,18 @@ impl Component for PmgPageMailView {
                     Ok(_) => {
                         // notify the (still mounted) spam list so it refreshes
                         if let Some(reload) = &self.reload {
-                            **reload.write() += 1;
+                            reload.bump();
+                        }
+                        match action {
+                            MailAction::MarkSeen => self.seen = true,
+                            MailAction::MarkUnseen => self.seen = false,
+                            _ => {}
                         }
                         tr!("Action '{0}' successful", action)
                     }
                     Err(err) => err.to_string(),
                 };
+                announce(&message);
                 ctx.link().show_snackbar(SnackBar::new().message(message));
                 true
             }
@@ -168,54 +317,207 @@ impl Component for PmgPageMailView {
                 changed
             }
             Msg::LoadImages(load_images) => {
-                let changed = self.load_images != load_images;
+                if self.load_images == load_images {
+                    return false;
+                }
                 self.load_images = load_images;
-                changed
+                self.load_content(ctx);
+                true
             }
-            Msg::ExternalImages(show_load_images) => {
-                let changed = self.show_load_images != show_load_images;
-                self.show_load_images = show_load_images;
-                changed
+            Msg::Content(id, load_images, result) => {
+                // a late answer for another mail or image setting
+                if id != ctx.props().id || load_images != self.load_images {
+                    return false;
+                }
+                // the flags come along with the content
+                if let Ok(content) = &result {
+                    let flags = MailFlags::from(&content.data);
+                    // kept once the images are loaded, to turn them off again
+                    self.show_load_images |= flags.external_images;
+                    self.seen = flags.seen;
+                    self.from = flags.from;
+                }
+                self.content = Some(
+                    result
+                        .map(|content| sandboxed_document(&content.html, load_images))
+                        .map_err(|err| err.to_string()),
+                );
+                true
+            }
+            Msg::Key(event) => {
+                let Some(shortcut) = Shortcut::from_event(&event) else {
+                    return false;
+                };
+                if !self.handle_shortcut(ctx, shortcut) {
+                    return false;
+                }
+                event.prevent_default();
+                true
+            }
+            Msg::CloseShortcuts => {
+                self.show_shortcuts = false;
+                true
+            }
+            Msg::ShowSender(show) => {
+                self.show_sender = show;
+                true
+            }
+            Msg::Action(action) => {
+                // the dialog needs the sender, which arrives with the content
+                match SenderList::from_action(action) {
+                    Some(list) if !self.from.is_empty() => {
+                        self.list_dialog = Some(list);
+                        return true;
+                    }
+                    _ => Self::run_action(ctx.link(), ctx.props().id.clone(), action),
+                }
+                false
+            }
+            Msg::CloseListDialog => {
+                self.list_dialog = None;
+                true
+            }
+            Msg::ShowActions(show) => {
+                self.show_actions = show;
+                true
+            }
+            Msg::CloseLink => {
+                self.link = None;
+                true
+            }
+            Msg::FrameLoaded => {
+                self.frame_loads += 1;
+                if self.frame_loads == 1 {
+                    return false;
+                }
+                // show the mail again instead of where it leads
+                self.frame_generation += 1;
+                self.frame_loads = 0;
+                let links = match &self.content {
+                    Some(Ok(document)) => document.links.as_slice(),
+                    _ => &[],
+                };
+                let frame = self.frame.cast::<HtmlIFrameElement>();
+                match frame.and_then(|frame| activated_link(&frame, links)) {
+                    Some(link) => self.link = Some(link),
+                    // e.g. by a refresh header of the mail
+                    None => ctx.link().show_snackbar(
+                        SnackBar::new().message(tr!("The mail tried to open another page")),
+                    ),
+                }
+                true
+            }
+            // close the topmost overlay
+            Msg::Back => {
+                if self.link.take().is_none()
+                    && self.pending_batch.take().is_none()
+                    && self.list_dialog.take().is_none()
+                    && !std::mem::take(&mut self.show_sender)
+                    && !std::mem::take(&mut self.show_actions)
+                {
+                    self.show_shortcuts = false;
+                }
+                true
+            }
+            Msg::EntryAdded(added) => {
+                let link = ctx.link().clone();
+                let id = ctx.props().id.clone();
+                let reload = self.reload.clone();
+                let mut others = ctx
+                    .link()
+                    .context::<LoadedMails>(Callback::noop())
+                    .map(|(loaded, _handle)| matching_mails(&loaded.0.read(), &added.entry, &id))
+                    .unwrap_or_default();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let mut message = tr!("Added {0} to {1}", added.entry, added.list.label());
+                    if let Some(action) = added.followup {
+                        if let Err(err) = mail_action(&id, action).await {
+                            message = err.to_string();
+                            // nothing to build on
+                            others.clear();
+                        }
+                    }
+                    if let Some(reload) = &reload {
+                        reload.bump();
+                    }
+                    announce(&message);
+                    link.show_snackbar(retroactive_snackbar(
+                        message,
+                        added.list,
+                        others,
+                        link.callback(|(ids, action)| Msg::ConfirmBatch(ids, action)),
+                    ));
+                });
+                false
+            }
+            Msg::ConfirmBatch(ids, action) => {
+                self.pending_batch = Some((ids, action));
+                true
             }
+            Msg::CancelBatch => {
+                self.pending_batch = None;
+                true
+            }
+            Msg::BatchAction(ids, action) => {
+                self.pending_batch = None;
+                let link = ctx.link().clone();
+                let reload = self.reload.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let message = match mail_action_batch(&ids, action).await {
+                        Ok(_) => tr!("Action '{0}' successful for {1} mails", action, ids.len()),
+                        Err(err) => err.to_string(),
+                    };
+                    if let Some(reload) = &reload {
+                        reload.bump();
+                    }
+                    announce(&message);
+                    link.show_snackbar(SnackBar::new().message(message));
+                });
+                true
+            }
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
+        // the two-pane layout keeps the view mounted while the selection changes
+        if ctx.props().id != old_props.id {
+            self.load_images = false;
+            self.show_load_images = false;
+            self.seen = false;
+            self.from.clear();
+            self.show_sender = false;
+            self.list_dialog = None;
+            self.show_actions = false;
+            self.pending_batch = None;
+            self.link = None;
+            self.load_content(ctx);
         }
+        true
+    }
+
+    fn rendered(&mut self, ctx: &Context<Self>, _first_render: bool) {
+        let open = self.overlay_open();
+        sync_history_guard(&mut self.history_guard, open, || {
+            ctx.link().callback(|_| Msg::Back)
+        });
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
-        let fab = FabMenu::new()
-            .main_icon_class("fa fa-bars")
-            .with_child(FabMenuEntry::new(
-                tr!("Deliver"),
-                "fa fa-paper-plane",
-                self.action_callback(ctx, MailAction::Deliver),
-            ))
-            .with_child(FabMenuEntry::new(
-                tr!("Delete"),
-                "fa fa-trash",
-                self.action_callback(ctx, MailAction::Delete),
-            ))
-            .with_child(FabMenuEntry::new(
-                tr!("Welcomelist"),
-                "fa fa-check",
-                self.action_callback(ctx, MailAction::Welcomelist),
-            ))
-            .with_child(FabMenuEntry::new(
-                tr!("Blocklist"),
-                "fa fa-times",
-                self.action_callback(ctx, MailAction::Blocklist),
-            ))
-            .with_child(FabMenuEntry::new(
-                tr!("Mark as Seen"),
-                "fa fa-eye",
-                self.action_callback(ctx, MailAction::MarkSeen),
-            ))
-            .with_child(FabMenuEntry::new(
-                tr!("Mark as Unseen"),
-                "fa fa-eye-slash",
-                self.action_callback(ctx, MailAction::MarkUnseen),
-            ));
+        let fab =
+            Fab::new("fa fa-bars").on_activate(ctx.link().callback(|_| Msg::ShowActions(true)));
 
         let mut app_bar = ApplicationBar::new().title(tr!("Preview"));
 
+        if !self.from.is_empty() {
+            app_bar.add_action(
+                Button::new_icon("fa fa-user")
+                    .class("circle")
+                    .attribute("aria-label", tr!("Sender Details"))
+                    .attribute("title", tr!("Sender Details"))
+                    .on_activate(ctx.link().callback(|_| Msg::ShowSender(true))),
+            );
+        }
+
         if self.show_load_images {
             let id = get_unique_element_id();
             app_bar.add_action(
@@ -248,9 +550,54 @@ impl Component for PmgPageMailView {
             );
         }
 
+        let help = self
+            .show_shortcuts
+            .then(|| shortcut_help(ctx.link().callback(|_| Msg::CloseShortcuts)));
+
+        let list_dialog: Option<Html> = self.list_dialog.map(|list| {
+            ListDialog::new(list, self.from.clone())
+                .on_close(ctx.link().callback(|_| Msg::CloseListDialog))
+                .on_done(ctx.link().callback(Msg::EntryAdded))
+                .into()
+        });
+
+        let sender_sheet: Option<Html> = self.show_sender.then(|| {
+            SenderSheet::new(self.from.clone())
+                .on_close(ctx.link().callback(|_| Msg::ShowSender(false)))
+                .into()
+        });
+
+        let link_sheet: Option<Html> = self.link.clone().map(|link| {
+            LinkSheet::new(link)
+                .on_close(ctx.link().callback(|_| Msg::CloseLink))
+                .into()
+        });
+
+        let actions: Option<Html> = self.show_actions.then(|| {
+            preview_action_sheet(
+                self.seen,
+                ctx.link().callback(Msg::Action),
+                ctx.link().callback(|_| Msg::ShowActions(false)),
+            )
+            .into()
+        });
+
+        let confirm: Option<Html> = self.pending_batch.as_ref().map(|(ids, action)| {
+            let ids = ids.clone();
+            let action = *action;
+            batch_confirm_dialog(
+                action,
+                ids.len(),
+                ctx.link()
+                    .callback(move |_| Msg::BatchAction(ids.clone(), action)),
+                ctx.link().callback(|_| Msg::CancelBatch),
+            )
+            .into()
+        });
+
         Scaffold::new()
             .application_bar(app_bar)
-            .body(self.content_view(ctx))
+            .body(html! {<>{self.content_view(ctx)}{help.map(Html::from)}{actions}{sender_sheet}{list_dialog}{confirm}{link_sheet}</>})
             .favorite_action_button(fab)
             .into()
     }
@@ -0,0 +1,485 @@
+use std::rc::Rc;
+
+use anyhow::{format_err, Error};
+use gloo_utils::document;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{HtmlElement, HtmlInputElement};
+
+use yew::virtual_dom::{VComp, VNode};
+
+use pwt::css::{AlignItems, FlexFit, JustifyContent, Opacity, Overflow};
+use pwt::prelude::*;
+use pwt::touch::{ApplicationBar, Scaffold, SnackBar, SnackBarContextExt};
+use pwt::widget::form::{Field, Form, FormContext};
+use pwt::widget::{error_message, Button, Column, Container, Dialog, Fa, Progress, Row};
+
+use crate::a11y::announce;
+use crate::history::{sync_history_guard, HistoryGuard};
+use crate::list_import::{export_csv, parse_import, ImportEntry, ImportStatus};
+use crate::sender_lists::{
+    add_entries_to_sender_list, add_to_sender_list, load_sender_list, remove_from_sender_list,
+    valid_entry, SenderList,
+};
+
+// entries per request of an import, large files take a few requests instead
+// of one per entry
+const IMPORT_CHUNK_SIZE: usize = 100;
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct PageSenderLists {}
+
+impl PageSenderLists {
+    pub fn new() -> Self {
+        yew::props!(Self {})
+    }
+}
+
+impl Default for PageSenderLists {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub enum Msg {
+    SelectList(SenderList),
+    LoadResult(Result<Vec<String>, Error>),
+    Add,
+    Remove(String),                          // entry
+    ActionResult(Result<(), Error>, String), // success message
+    ChooseFile,
+    FileSelected(Event),
+    FileLoaded(String, Result<String, Error>), // file name, content
+    CloseImport,
+    SubmitImport,
+    ImportDone(usize, usize), // added, failed
+    Export,
+}
+
+pub struct PmgPageSenderLists {
+    list: SenderList,
+    entries: Option<Result<Vec<String>, Error>>,
+    form_context: FormContext,
+    file_input: NodeRef,
+    // parsed import waiting for confirmation
+    import: Option<Vec<ImportEntry>>,
+    importing: bool,
+    // history entry while the import preview is open
+    history_guard: Option<HistoryGuard>,
+}
+
+// Offers the text as file download via a temporary link.
+fn download(file_name: &str, mime_type: &str, content: &str) {
+    let url = format!(
+        "data:{mime_type};charset=utf-8,{}",
+        utf8_percent_encode(content, NON_ALPHANUMERIC)
+    );
+    let Ok(link) = document().create_element("a") else {
+        return;
+    };
+    let _ = link.set_attribute("href", &url);
+    let _ = link.set_attribute("download", file_name);
+    if let Ok(link) = link.dyn_into::<HtmlElement>() {
+        link.click();
+    }
+}
+
+impl PmgPageSenderLists {
+    fn load(&self, ctx: &Context<Self>) {
+        let link = ctx.link().clone();
+        let list = self.list;
+        wasm_bindgen_futures::spawn_local(async move {
+            link.send_message(Msg::LoadResult(load_sender_list(list).await));
+        });
+    }
+
+    fn current_entries(&self) -> &[String] {
+        match &self.entries {
+            Some(Ok(entries)) => entries,
+            _ => &[],
+        }
+    }
+
+    fn list_tabs(&self, ctx: &Context<Self>) -> Html {
+        let mut tabs = Row::new().gap(1).padding(1).attribute("role", "tablist");
+        for list in [SenderList::Welcomelist, SenderList::Blocklist] {
+            let selected = self.list == list;
+            tabs.add_child(
+                Button::new(list.label())
+                    .class(FlexFit)
+                    .class(if selected {
+                        "pwt-scheme-primary"
+                    } else {
+                        "pwt-button-text"
+                    })
+                    .attribute("role", "tab")
+                    .attribute("aria-selected", selected.to_string())
+                    .on_activate(ctx.link().callback(move |_| Msg::SelectList(list))),
+            );
+        }
+        tabs.into()
+    }
+
+    fn add_form(&self, ctx: &Context<Self>) -> Html {
+        let panel = Row::new()
+            .gap(1)
+            .padding_x(2)
+            .class(AlignItems::Center)
+            .with_child(
+                Field::new()
+                    .name("address")
+                    .class(FlexFit)
+                    .placeholder(tr!("Address or pattern")),
+            )
+            .with_child(
+                Button::new(tr!("Add"))
+                    .icon_class("fa fa-plus")
+                    .on_activate(ctx.link().callback(|_| Msg::Add)),
+            );
+
+        Form::new()
+            .form_context(self.form_context.clone())
+            .with_child(panel)
+            .into()
+    }
+
+    fn entry_list(&self, ctx: &Context<Self>) -> Html {
+        let entries = match &self.entries {
+            Some(Ok(entries)) => entries,
+            Some(Err(err)) => return error_message(&err.to_string()).into(),
+            None => return Progress::new().into(),
+        };
+        if entries.is_empty() {
+            return Container::new()
+                .padding(2)
+                .class(Opacity::Half)
+                .with_child(tr!("No entries"))
+                .into();
+        }
+
+        let mut list = Column::new()
+            .class(FlexFit)
+            .class(Overflow::Auto)
+            .padding_x(2)
+            .attribute("role", "list");
+        for entry in entries {
+            let remove = entry.clone();
+            list.add_child(
+                Row::new()
+                    .class(AlignItems::Center)
+                    .gap(1)
+                    .attribute("role", "listitem")
+                    .with_child(
+                        Container::new()
+                            .class(FlexFit)
+                            .class("pwt-text-truncate")
+                            .with_child(entry),
+                    )
+                    .with_child(
+                        Button::new_icon("fa fa-trash")
+                            .class("circle")
+                            .attribute("aria-label", tr!("Remove {0}", entry))
+                            .on_activate(ctx.link().callback(move |_| Msg::Remove(remove.clone()))),
+                    ),
+            );
+        }
+        list.into()
+    }
+
+    fn import_preview(&self, ctx: &Context<Self>, entries: &[ImportEntry]) -> Html {
+        let count = |status| entries.iter().filter(|e| e.status == status).count();
+        let new = count(ImportStatus::New);
+
+        let mut list = Column::new()
+            .gap(1)
+            .class(Overflow::Auto)
+            .style("max-height", "50vh");
+        for entry in entries {
+            let (icon, label) = match entry.status {
+                ImportStatus::New => ("plus-circle", tr!("New")),
+                ImportStatus::Duplicate => ("clone", tr!("Duplicate")),
+                ImportStatus::Invalid => ("exclamation-triangle", tr!("Invalid")),
+            };
+            let mut row = Row::new()
+                .gap(1)
+                .class(AlignItems::Center)
+                .with_child(Fa::new(icon))
+                .with_child(
+                    Container::new()
+                        .class(FlexFit)
+                        .class("pwt-text-truncate")
+                        .with_child(&entry.value),
+                )
+                .with_child(
+                    Container::new()
+                        .class("pwt-font-label-small")
+                        .with_child(label),
+                );
+            if entry.status != ImportStatus::New {
+                row = row.class(Opacity::Half);
+            }
+            list.add_child(row);
+        }
+
+        let panel = Column::new()
+            .padding(2)
+            .gap(2)
+            .min_width("min(30rem, 80vw)")
+            .with_child(tr!(
+                "{0} new, {1} duplicates, {2} invalid",
+                new,
+                count(ImportStatus::Duplicate),
+                count(ImportStatus::Invalid)
+            ))
+            .with_child(list)
+            .with_child(
+                Row::new()
+                    .gap(1)
+                    .class(JustifyContent::FlexEnd)
+                    .with_child(
+                        Button::new(tr!("Cancel"))
+                            .class("pwt-button-text")
+                            .on_activate(ctx.link().callback(|_| Msg::CloseImport)),
+                    )
+                    .with_child(
+                        Button::new(tr!("Import {0} entries", new))
+                            .class("pwt-scheme-primary")
+                            .disabled(new == 0 || self.importing)
+                            .on_activate(ctx.link().callback(|_| Msg::SubmitImport)),
+                    ),
+            );
+
+        Dialog::new(tr!("Import into {0}", self.list.label()))
+            .on_close(ctx.link().callback(|_| Msg::CloseImport))
+            .with_child(panel)
+            .into()
+    }
+}
+
+impl Component for PmgPageSenderLists {
+    type Message = Msg;
+    type Properties = PageSenderLists;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let me = Self {
+            list: SenderList::Welcomelist,
+            entries: None,
+            form_context: FormContext::new(),
+            file_input: NodeRef::default(),
+            import: None,
+            importing: false,
+            history_guard: None,
+        };
+        me.load(ctx);
+        me
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::SelectList(list) => {
+                if self.list == list {
+                    return false;
+                }
+                self.list = list;
+                self.entries = None;
+                self.load(ctx);
+            }
+            Msg::LoadResult(result) => self.entries = Some(result),
+            Msg::Add => {
+                let entry = self
+                    .form_context
+                    .read()
+                    .get_field_value("address")
+                    .and_then(|value| value.as_str().map(|s| s.trim().to_lowercase()))
+                    .unwrap_or_default();
+                if !valid_entry(&entry) {
+                    ctx.link().show_snackbar(
+                        SnackBar::new()
+                            .message(tr!("'{0}' is not a valid address or pattern", entry)),
+                    );
+                    return false;
+                }
+                // start over with an empty field
+                self.form_context = FormContext::new();
+                let link = ctx.link().clone();
+                let list = self.list;
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = add_to_sender_list(list, &entry).await;
+                    let message = tr!("Added {0} to {1}", entry, list.label());
+                    link.send_message(Msg::ActionResult(result, message));
+                });
+            }
+            Msg::Remove(entry) => {
+                let link = ctx.link().clone();
+                let list = self.list;
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = remove_from_sender_list(list, &entry).await;
+                    let message = tr!("Removed {0} from {1}", entry, list.label());
+                    link.send_message(Msg::ActionResult(result, message));
+                });
+                return false;
+            }
+            Msg::ActionResult(result, message) => {
+                let message = match result {
+                    Ok(()) => message,
+                    Err(err) => err.to_string(),
+                };
+                announce(&message);
+                ctx.link().show_snackbar(SnackBar::new().message(message));
+                self.load(ctx);
+                return false;
+            }
+            Msg::ChooseFile => {
+                if let Some(input) = self.file_input.cast::<HtmlInputElement>() {
+                    input.click();
+                }
+                return false;
+            }
+            Msg::FileSelected(event) => {
+                let input: HtmlInputElement = event.target_unchecked_into();
+                let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                    return false;
+                };
+                // allow choosing the same file again
+                input.set_value("");
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let content = JsFuture::from(file.text())
+                        .await
+                        .map(|text| text.as_string().unwrap_or_default())
+                        .map_err(|err| format_err!("could not read file: {err:?}"));
+                    link.send_message(Msg::FileLoaded(file.name(), content));
+                });
+                return false;
+            }
+            Msg::FileLoaded(file_name, result) => match result {
+                Ok(content) => {
+                    let entries = parse_import(&file_name, &content, self.current_entries());
+                    if entries.is_empty() {
+                        ctx.link().show_snackbar(
+                            SnackBar::new().message(tr!("No addresses found in {0}", file_name)),
+                        );
+                    } else {
+                        self.import = Some(entries);
+                    }
+                }
+                Err(err) => {
+                    ctx.link()
+                        .show_snackbar(SnackBar::new().message(err.to_string()));
+                    return false;
+                }
+            },
+            Msg::CloseImport => {
+                if self.importing {
+                    return false;
+                }
+                self.import = None;
+            }
+            Msg::SubmitImport => {
+                let Some(entries) = &self.import else {
+                    return false;
+                };
+                let new: Vec<String> = entries
+                    .iter()
+                    .filter(|entry| entry.status == ImportStatus::New)
+                    .map(|entry| entry.value.clone())
+                    .collect();
+                self.importing = true;
+                let link = ctx.link().clone();
+                let list = self.list;
+                wasm_bindgen_futures::spawn_local(async move {
+                    let (mut added, mut failed) = (0, 0);
+                    for chunk in new.chunks(IMPORT_CHUNK_SIZE) {
+                        match add_entries_to_sender_list(list, chunk).await {
+                            Ok(()) => added += chunk.len(),
+                            Err(err) => {
+                                log::error!("import of {} entries failed: {err}", chunk.len());
+                                failed += chunk.len();
+                            }
+                        }
+                    }
+                    link.send_message(Msg::ImportDone(added, failed));
+                });
+            }
+            Msg::ImportDone(added, failed) => {
+                self.importing = false;
+                self.import = None;
+                let message = if failed == 0 {
+                    tr!("Imported {0} entries", added)
+                } else {
+                    tr!("Imported {0} entries, {1} failed", added, failed)
+                };
+                announce(&message);
+                ctx.link().show_snackbar(SnackBar::new().message(message));
+                self.load(ctx);
+            }
+            Msg::Export => {
+                let file_name = match self.list {
+                    SenderList::Welcomelist => "welcomelist.csv",
+                    SenderList::Blocklist => "blocklist.csv",
+                };
+                download(file_name, "text/csv", &export_csv(self.current_entries()));
+                return false;
+            }
+        }
+        true
+    }
+
+    fn rendered(&mut self, ctx: &Context<Self>, _first_render: bool) {
+        sync_history_guard(&mut self.history_guard, self.import.is_some(), || {
+            ctx.link().callback(|_| Msg::CloseImport)
+        });
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let mut app_bar = ApplicationBar::new().title(tr!("Sender Lists"));
+        app_bar.add_action(
+            Button::new_icon("fa fa-upload")
+                .class("circle")
+                .attribute("aria-label", tr!("Import"))
+                .attribute("title", tr!("Import"))
+                .on_activate(ctx.link().callback(|_| Msg::ChooseFile)),
+        );
+        app_bar.add_action(
+            Button::new_icon("fa fa-download")
+                .class("circle")
+                .attribute("aria-label", tr!("Export"))
+                .attribute("title", tr!("Export"))
+                .disabled(!matches!(self.entries, Some(Ok(_))))
+                .on_activate(ctx.link().callback(|_| Msg::Export)),
+        );
+
+        let file_input = html! {
+            <input
+                ref={self.file_input.clone()}
+                type="file"
+                accept=".csv,.txt,.vcf,text/csv,text/plain,text/vcard"
+                style="display: none;"
+                onchange={ctx.link().callback(Msg::FileSelected)}
+            />
+        };
+
+        let preview: Option<Html> = self
+            .import
+            .as_ref()
+            .map(|entries| self.import_preview(ctx, entries));
+
+        let body = Column::new()
+            .class(FlexFit)
+            .gap(1)
+            .with_child(self.list_tabs(ctx))
+            .with_child(self.add_form(ctx))
+            .with_child(self.entry_list(ctx))
+            .with_child(html! {<>{file_input}{preview}</>});
+
+        Scaffold::new().application_bar(app_bar).body(body).into()
+    }
+}
+
+impl From<PageSenderLists> for VNode {
+    fn from(val: PageSenderLists) -> Self {
+        let comp = VComp::new::<PmgPageSenderLists>(Rc::new(val), None);
+        VNode::from(comp)
+    }
+}
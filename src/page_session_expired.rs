@@ -0,0 +1,187 @@
+use std::rc::Rc;
+
+use anyhow::Error;
+use serde_json::json;
+
+use yew::html::{IntoEventCallback, IntoPropValue};
+use yew::virtual_dom::{VComp, VNode};
+
+use pwt::css::{FlexFit, JustifyContent};
+use pwt::prelude::*;
+use pwt::touch::{ApplicationBar, Scaffold};
+use pwt::widget::form::{Field, Form, FormContext, InputType};
+use pwt::widget::{error_message, get_unique_element_id, Button, Column, Container, FieldLabel};
+
+use crate::a11y::announce;
+
+/// Shown once the server no longer accepts the session, usually because the
+/// ticket of a spam report link ran out.
+///
+/// Offers to send a new link or to log in with credentials instead.
+#[derive(Clone, PartialEq, Properties)]
+pub struct PageSessionExpired {
+    /// Address to send a new link to.
+    #[prop_or_default]
+    mail: Option<String>,
+    #[prop_or_default]
+    on_login: Option<Callback<()>>,
+}
+
+impl PageSessionExpired {
+    pub fn new() -> Self {
+        yew::props!(Self {})
+    }
+
+    pub fn mail(mut self, mail: impl IntoPropValue<Option<String>>) -> Self {
+        self.mail = mail.into_prop_value();
+        self
+    }
+
+    pub fn on_login(mut self, cb: impl IntoEventCallback<()>) -> Self {
+        self.on_login = cb.into_event_callback();
+        self
+    }
+}
+
+impl Default for PageSessionExpired {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub enum Msg {
+    SendLink,
+    SendResult(String, Result<(), Error>), // address
+}
+
+pub struct PmgPageSessionExpired {
+    form_context: FormContext,
+    sending: bool,
+    // outcome of the last link request
+    result: Option<Result<String, String>>,
+}
+
+impl Component for PmgPageSessionExpired {
+    type Message = Msg;
+    type Properties = PageSessionExpired;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        announce(&tr!("Your session has expired"));
+        Self {
+            form_context: FormContext::new(),
+            sending: false,
+            result: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::SendLink => {
+                let mail = self
+                    .form_context
+                    .read()
+                    .get_field_value("mail")
+                    .and_then(|value| value.as_str().map(|mail| mail.trim().to_string()))
+                    .unwrap_or_default();
+                if mail.is_empty() {
+                    self.result = Some(Err(tr!("Please enter your email address")));
+                    return true;
+                }
+                self.sending = true;
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    // works without a valid session
+                    let result = proxmox_yew_comp::http_post::<()>(
+                        "/quarantine/sendlink",
+                        Some(json!({ "mail": mail })),
+                    )
+                    .await;
+                    link.send_message(Msg::SendResult(mail, result));
+                });
+            }
+            Msg::SendResult(mail, result) => {
+                self.sending = false;
+                let result = result
+                    .map(|_| tr!("A new link was sent to {0}, please check your inbox", mail))
+                    .map_err(|err| err.to_string());
+                match &result {
+                    Ok(message) | Err(message) => announce(message),
+                }
+                self.result = Some(result);
+            }
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+        let mail_id = get_unique_element_id();
+
+        let mut body = Column::new()
+            .class(FlexFit)
+            .padding(2)
+            .gap(2)
+            .with_child(
+                Container::new()
+                    .class("pwt-font-title-large")
+                    .with_child(tr!("Your session has expired")),
+            )
+            .with_child(tr!(
+                "Links from spam reports only give access to the quarantine for a limited time. You can request a new link by mail, or log in with your credentials if you have an account."
+            ))
+            .with_child(FieldLabel::new(tr!("Email address")).id(mail_id.clone()))
+            .with_child(
+                Form::new().form_context(self.form_context.clone()).with_child(
+                    Field::new()
+                        .name("mail")
+                        .label_id(mail_id)
+                        .input_type(InputType::Email)
+                        .default(props.mail.clone().unwrap_or_default()),
+                ),
+            );
+
+        body.add_optional_child(match &self.result {
+            Some(Ok(message)) => Some(Html::from(message.clone())),
+            Some(Err(err)) => Some(error_message(err).into()),
+            None => None,
+        });
+
+        body.add_child(
+            Column::new()
+                .gap(1)
+                .class(JustifyContent::Stretch)
+                .with_child(
+                    Button::new(tr!("Send New Link"))
+                        .icon_class("fa fa-envelope")
+                        .class("pwt-scheme-primary")
+                        .disabled(self.sending)
+                        .on_activate(ctx.link().callback(|_| Msg::SendLink)),
+                )
+                .with_child(
+                    Button::new(tr!("Log In with Credentials"))
+                        .icon_class("fa fa-sign-in")
+                        .class("pwt-button-outline")
+                        .on_activate({
+                            let on_login = props.on_login.clone();
+                            move |_| {
+                                if let Some(on_login) = &on_login {
+                                    on_login.emit(());
+                                }
+                            }
+                        }),
+                ),
+        );
+
+        Scaffold::new()
+            .application_bar(ApplicationBar::new().title(tr!("Session Expired")))
+            .body(body)
+            .into()
+    }
+}
+
+impl From<PageSessionExpired> for VNode {
+    fn from(val: PageSessionExpired) -> Self {
+        let comp = VComp::new::<PmgPageSessionExpired>(Rc::new(val), None);
+        VNode::from(comp)
+    }
+}
@@ -1,26 +1,46 @@
+use std::collections::HashMap;
 use std::rc::Rc;
 
 use anyhow::Error;
 use gloo_utils::document;
 use js_sys::Date;
-use wasm_bindgen::JsValue;
+use serde::{Deserialize, Serialize};
+use web_sys::HtmlSelectElement;
 
+use yew::html::IntoPropValue;
 use yew::platform::spawn_local;
 use yew::prelude::*;
 use yew::virtual_dom::{VComp, VNode};
-use yew_router::scope_ext::RouterScopeExt;
+use yew_router::history::Location;
+use yew_router::scope_ext::{LocationHandle, RouterScopeExt};
+use yew_router::Routable;
 
 use pwt::css::{AlignItems, ColorScheme, FlexFit, JustifyContent};
 use pwt::prelude::*;
-use pwt::touch::{ApplicationBar, Fab, Scaffold};
-use pwt::widget::form::{Field, Form, FormContext, InputType};
+use pwt::touch::{ApplicationBar, Fab, Scaffold, SnackBar, SnackBarContextExt};
+use pwt::widget::form::{Checkbox, Field, Form, FormContext, InputType};
 use pwt::widget::menu::{Menu, MenuButton, MenuItem};
-use pwt::widget::{Button, Column, Dialog, Image, LanguageSelector, Row, ThemeModeSelector};
+use pwt::widget::{
+    get_unique_element_id, Button, Column, Container, Dialog, Fa, FieldLabel, Image,
+    LanguageSelector, Row, ThemeModeSelector,
+};
 
 use proxmox_subscription::{SubscriptionInfo, SubscriptionStatus};
-use proxmox_yew_comp::{http_get, Markdown};
+use proxmox_yew_comp::Markdown;
 
-use crate::{Route, SpamList};
+use crate::a11y::prefers_reduced_motion;
+use crate::calendar::RangeCalendar;
+use crate::date_format::{
+    day_key, day_start, format_date, format_time, parse_date_time, time_key, time_zones,
+};
+use crate::history::{sync_history_guard, HistoryGuard};
+use crate::idle::IDLE_TIMEOUT_CHOICES;
+use crate::notifications::{self, clear_badge, notifications_supported, request_permission};
+use crate::session::{format_remaining, http_get, log_out, session_remaining};
+use crate::settings::Settings;
+use crate::spam_list::ListSort;
+use crate::tab_sync::{TabEvent, TabSubscription};
+use crate::{PageMailView, Route, ServerConfig, SpamList};
 
 const ABOUT_TEXT: &str =
     "This is the end-user email quarantine interface provided by your email provider.
@@ -45,12 +65,54 @@ provider.
 
 Proxmox Server Solutions GmbH develops the software and does not operate email services for users.";
 
+/// List state carried in the query string, so links to the list and the
+/// browser history keep the range and filters.
+///
+/// The previewed mail is no part of it, its id is in the path of
+/// [`Route::ViewMail`], which carries this query along. Neither is the
+/// selection for batch actions, which its own history entry closes on back.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ListQuery {
+    /// Start of the date range (epoch).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<u64>,
+    /// End of the date range (epoch).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<u64>,
+    /// Sender filter, see [`crate::spam_list::sender_matches`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sender: Option<String>,
+    /// Order of the mails, newest first if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sort: Option<ListSort>,
+    /// Quarantine address whose mails are listed, the user's own if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+}
+
 #[derive(Clone, PartialEq, Properties)]
-pub struct PageSpamList {}
+pub struct PageSpamList {
+    /// Show the list and the preview of the selected mail side by side.
+    #[prop_or_default]
+    two_pane: bool,
+    /// Id of the mail to preview and highlight in the list.
+    #[prop_or_default]
+    selected: Option<String>,
+}
 
 impl PageSpamList {
     pub fn new() -> Self {
-        Self {}
+        yew::props!(Self {})
+    }
+
+    pub fn two_pane(mut self, two_pane: bool) -> Self {
+        self.two_pane = two_pane;
+        self
+    }
+
+    pub fn selected(mut self, id: impl IntoPropValue<Option<String>>) -> Self {
+        self.selected = id.into_prop_value();
+        self
     }
 }
 
@@ -67,13 +129,73 @@ pub enum ViewState {
     ShowSubscriptionNotice,
     ShowLanguageSelect,
     ShowAbout,
+    ShowSettings,
+}
+#[derive(Clone, Copy, PartialEq)]
+pub enum DatePreset {
+    Today,
+    Last24Hours,
+    Last7Days,
+    Last30Days,
+    WholeQuarantine,
+}
+
+impl DatePreset {
+    fn label(&self) -> String {
+        match self {
+            DatePreset::Today => tr!("Today"),
+            DatePreset::Last24Hours => tr!("Last 24 hours"),
+            DatePreset::Last7Days => tr!("Last 7 days"),
+            DatePreset::Last30Days => tr!("Last 30 days"),
+            DatePreset::WholeQuarantine => tr!("Whole quarantine"),
+        }
+    }
+
+    // (start, end) epoch
+    fn range(&self) -> (i64, i64) {
+        let now = (Date::now() / 1000.0) as i64;
+        let today = day_start(now);
+        // go to noon of the earlier day, so a DST switch in between does not
+        // make us miss its start
+        let days_before = |days: i64| day_start(today - days * 86400 + 12 * 3600);
+        let tomorrow = day_start(today + 36 * 3600);
+        match self {
+            DatePreset::Today => (today, tomorrow),
+            DatePreset::Last24Hours => (now - 86400, now + 60),
+            DatePreset::Last7Days => (days_before(6), tomorrow),
+            DatePreset::Last30Days => (days_before(29), tomorrow),
+            // the quarantine does not keep mails that long anyway
+            DatePreset::WholeQuarantine => (0, tomorrow),
+        }
+    }
 }
+
 pub struct PmgPageSpamList {
     state: ViewState,
     start_date: f64,
     end_date: f64,
+    // whether the range was chosen by the user rather than the default
+    custom_range: bool,
+    // restrict the range to a time of day instead of whole days
+    with_time: bool,
+    // first and last day picked in the calendar of the date range form
+    picked: Option<(i64, i64)>,
+    // inline errors of the date range form, by field name
+    date_errors: HashMap<&'static str, String>,
     form_context: FormContext,
     subscription_result: Option<bool>,
+    settings: Settings,
+    sender: Option<String>,
+    sort: ListSort,
+    // quarantine address to list, the user's own if unset
+    address: Option<String>,
+    // history entry while a dialog is open
+    history_guard: Option<HistoryGuard>,
+    _location_listener: Option<LocationHandle>,
+    // picks up settings changed in other tabs
+    _tab_subscription: TabSubscription,
+    // a dialog or sheet of the list is open, which takes the keyboard
+    list_overlay: bool,
 }
 
 pub enum Msg {
@@ -82,59 +204,534 @@ pub enum Msg {
     ShowSubscriptionNotice,
     CloseDialog,
     ApplyDate,
+    PickRange((i64, i64)), // first and last day
+    DatePreset(DatePreset),
+    WithTime(bool), // on/off
+    ResetRange,
     SubscriptionResult(Result<SubscriptionInfo, Error>),
     SwitchToDesktop,
     ShowLanguageSelect,
     ShowAbout,
+    ShowSettings,
+    Notifications(bool),          // on/off
+    NotificationPermission(bool), // granted
+    ReducedMotion(bool),          // on/off
+    TimeZone(String),             // empty for the device time zone
+    SettingsChanged,
+    IdleTimeout(String), // minutes
+    ToggleGrouped,
+    ToggleSort,
+    ShowStatistics,
+    ShowSenderLists,
+    ClearSender,
+    ClearAddress,
+    LocationChanged(Location),
+    ListOverlay(bool), // open
     Logout,
 }
 
-fn epoch_to_date_string(epoch: f64) -> String {
-    let start_date = Date::new(&JsValue::from_f64(epoch));
-    format!(
-        "{:04}-{:02}-{:02}",
-        start_date.get_full_year(),
-        start_date.get_month() + 1,
-        start_date.get_date(),
-    )
-}
 impl PmgPageSpamList {
+    // Takes the list state from the query string, anything missing or
+    // invalid falls back to the defaults.
+    fn apply_query(&mut self, location: &Location) {
+        // other pages stacked on top carry their own parameters
+        if !matches!(
+            Route::recognize(&location.path()),
+            Some(Route::SpamList | Route::ViewMail { .. })
+        ) {
+            return;
+        }
+        let query = location.query::<ListQuery>().unwrap_or_else(|err| {
+            log::error!("ignoring invalid list parameters: {err}");
+            ListQuery::default()
+        });
+
+        let (start, end) = match (query.start, query.end) {
+            (Some(start), Some(end)) if start < end => {
+                self.custom_range = true;
+                (start as i64, end as i64)
+            }
+            _ => {
+                self.custom_range = false;
+                DatePreset::Last7Days.range()
+            }
+        };
+        self.start_date = start as f64 * 1000.0;
+        self.end_date = end as f64 * 1000.0;
+        // a range not on day boundaries was picked with a time of day
+        self.with_time =
+            self.custom_range && start != 0 && (day_start(start) != start || day_start(end) != end);
+        self.sender = query
+            .sender
+            .map(|sender| sender.trim().to_string())
+            .filter(|sender| !sender.is_empty());
+        self.sort = query.sort.unwrap_or_default();
+        self.address = query
+            .address
+            .map(|address| address.trim().to_string())
+            .filter(|address| {
+                let valid = address.contains('@') && !address.contains(char::is_whitespace);
+                if !valid {
+                    log::error!("ignoring invalid address '{address}'");
+                }
+                valid
+            });
+    }
+
+    fn query(&self) -> ListQuery {
+        let range = self.custom_range.then(|| {
+            (
+                (self.start_date / 1000.0) as u64,
+                (self.end_date / 1000.0) as u64,
+            )
+        });
+        ListQuery {
+            start: range.map(|(start, _)| start),
+            end: range.map(|(_, end)| end),
+            sender: self.sender.clone(),
+            sort: (self.sort != ListSort::default()).then_some(self.sort),
+            address: self.address.clone(),
+        }
+    }
+
+    // Records the list state as a new history entry, so the back button
+    // returns to the previous one.
+    fn push_query(&mut self, ctx: &Context<Self>) {
+        let navigator = ctx.link().navigator().unwrap();
+        let route = ctx.link().route::<Route>().unwrap_or(Route::SpamList);
+        let query = self.query();
+        // the entry of a dialog that was just closed is reused
+        let result = match self.history_guard.take() {
+            Some(guard) if !guard.popped() => {
+                guard.release();
+                navigator.replace_with_query(&route, &query)
+            }
+            _ => navigator.push_with_query(&route, &query),
+        };
+        if let Err(err) = result {
+            log::error!("could not update list parameters: {err}");
+        }
+    }
+
+    fn sender_chip(&self, ctx: &Context<Self>) -> Option<Html> {
+        let sender = self.sender.as_ref()?;
+        Some(
+            Row::new()
+                .class(AlignItems::Center)
+                .gap(1)
+                .padding_x(2)
+                .padding_y(1)
+                .border_bottom(true)
+                .with_child(Fa::new("filter"))
+                .with_child(
+                    Container::new()
+                        .class(FlexFit)
+                        .class("pwt-text-truncate")
+                        .with_child(tr!("Sender: {0}", sender)),
+                )
+                .with_child(
+                    Button::new_icon("fa fa-times")
+                        .class("circle")
+                        .attribute("aria-label", tr!("Remove filter"))
+                        .on_activate(ctx.link().callback(|_| Msg::ClearSender)),
+                )
+                .into(),
+        )
+    }
+
+    fn address_chip(&self, ctx: &Context<Self>) -> Option<Html> {
+        let address = self.address.as_ref()?;
+        Some(
+            Row::new()
+                .class(AlignItems::Center)
+                .gap(1)
+                .padding_x(2)
+                .padding_y(1)
+                .border_bottom(true)
+                .with_child(Fa::new("envelope-o"))
+                .with_child(
+                    Container::new()
+                        .class(FlexFit)
+                        .class("pwt-text-truncate")
+                        .with_child(tr!("Quarantine of {0}", address)),
+                )
+                .with_child(
+                    Button::new_icon("fa fa-times")
+                        .class("circle")
+                        .attribute("aria-label", tr!("Show own quarantine"))
+                        .on_activate(ctx.link().callback(|_| Msg::ClearAddress)),
+                )
+                .into(),
+        )
+    }
+
+    fn range_chip(&self, ctx: &Context<Self>) -> Option<Html> {
+        if !self.custom_range {
+            return None;
+        }
+        let start = (self.start_date / 1000.0) as i64;
+        let end = (self.end_date / 1000.0) as i64;
+        let label = if start == 0 {
+            DatePreset::WholeQuarantine.label()
+        } else if self.with_time {
+            tr!(
+                "{0} {1} – {2} {3}",
+                format_date(start),
+                format_time(start),
+                format_date(end - 60),
+                format_time(end - 60)
+            )
+        } else {
+            // the end is exclusive, show the last included day
+            tr!("{0} – {1}", format_date(start), format_date(end - 1))
+        };
+        Some(
+            Row::new()
+                .class(AlignItems::Center)
+                .gap(1)
+                .padding_x(2)
+                .padding_y(1)
+                .border_bottom(true)
+                .with_child(Fa::new("calendar"))
+                .with_child(
+                    Container::new()
+                        .class(FlexFit)
+                        .class("pwt-text-truncate")
+                        .with_child(label),
+                )
+                .with_child(
+                    Button::new_icon("fa fa-times")
+                        .class("circle")
+                        .attribute("aria-label", tr!("Reset date range"))
+                        .on_activate(ctx.link().callback(|_| Msg::ResetRange)),
+                )
+                .into(),
+        )
+    }
+
+    fn field_error(&self, name: &str) -> Option<Html> {
+        let error = self.date_errors.get(name)?;
+        Some(
+            Container::new()
+                .class("pwt-font-label-small")
+                .style("color", "var(--pwt-color-error)")
+                .attribute("role", "alert")
+                .with_child(error)
+                .into(),
+        )
+    }
+
     fn date_range_form(&self, ctx: &Context<Self>) -> Html {
-        let start_date = epoch_to_date_string(self.start_date);
-        let end_date = epoch_to_date_string(self.end_date);
+        let start = (self.start_date / 1000.0) as i64;
+        let end = (self.end_date / 1000.0) as i64;
+        // the end is exclusive, the form shows the last included minute
+        let end_time = if self.with_time {
+            time_key(end - 60)
+        } else {
+            String::from("23:59")
+        };
+
+        let mut presets = Row::new().gap(1).style("flex-wrap", "wrap");
+        for preset in [
+            DatePreset::Today,
+            DatePreset::Last24Hours,
+            DatePreset::Last7Days,
+            DatePreset::Last30Days,
+            DatePreset::WholeQuarantine,
+        ] {
+            presets.add_child(
+                Button::new(preset.label())
+                    .class("pwt-button-outline")
+                    .on_activate(ctx.link().callback(move |_| Msg::DatePreset(preset))),
+            );
+        }
 
-        let panel = Column::new()
+        let with_time_id = get_unique_element_id();
+        let mut panel = Column::new()
             .padding(2)
             .gap(1)
             .min_width("70vw")
             .class("pwt-flex-fill")
-            .with_child(tr!("From:"))
-            .with_child(
-                Field::new()
-                    .name("from")
-                    .default(start_date)
-                    .input_type(InputType::Date),
-            )
-            .with_child(tr!("To:"))
+            .with_child(presets)
             .with_child(
-                Field::new()
-                    .name("to")
-                    .default(end_date)
-                    .input_type(InputType::Date),
-            )
-            .with_child(
-                Row::new().class("pwt-pt-2").with_flex_spacer().with_child(
-                    Button::new(tr!("Apply"))
-                        .class("pwt-scheme-primary")
-                        .onclick(ctx.link().callback(|_| Msg::ApplyDate)),
-                ),
+                RangeCalendar::new()
+                    .range(self.picked)
+                    .on_change(ctx.link().callback(Msg::PickRange)),
+            );
+        panel.add_optional_child(self.field_error("from"));
+        if self.with_time {
+            panel.add_child(
+                Row::new()
+                    .class(AlignItems::Center)
+                    .gap(1)
+                    .with_child(tr!("From:"))
+                    .with_child(
+                        Field::new()
+                            .name("from_time")
+                            .default(time_key(start))
+                            .input_type(InputType::Time),
+                    )
+                    .with_child(tr!("To:"))
+                    .with_child(
+                        Field::new()
+                            .name("to_time")
+                            .default(end_time)
+                            .input_type(InputType::Time),
+                    ),
             );
+        }
+        panel.add_optional_child(self.field_error("to"));
+        panel.add_child(
+            Row::new()
+                .class(AlignItems::Center)
+                .gap(1)
+                .with_child(
+                    Checkbox::new()
+                        .label_id(with_time_id.clone())
+                        .checked(self.with_time)
+                        .on_change(ctx.link().callback(Msg::WithTime)),
+                )
+                .with_child(FieldLabel::new(tr!("Specify time of day")).id(with_time_id)),
+        );
+        panel.add_child(
+            Row::new().class("pwt-pt-2").with_flex_spacer().with_child(
+                Button::new(tr!("Apply"))
+                    .class("pwt-scheme-primary")
+                    .onclick(ctx.link().callback(|_| Msg::ApplyDate)),
+            ),
+        );
 
         Form::new()
             .form_context(self.form_context.clone())
             .with_child(panel)
             .into()
     }
+
+    // reads and checks the date range form, collecting errors per field
+    fn read_date_range(&mut self) -> Option<(i64, i64)> {
+        self.date_errors.clear();
+        let Some((first, last)) = self.picked else {
+            self.date_errors
+                .insert("from", tr!("Please select a range in the calendar"));
+            return None;
+        };
+        if !self.with_time {
+            // make the end exclusive
+            return Some((first, day_start(last + 36 * 3600)));
+        }
+
+        let form = self.form_context.read();
+        let time = |name: &str| {
+            form.get_field_value(name)
+                .and_then(|value| value.as_str().map(String::from))
+                .unwrap_or_default()
+        };
+        let start = parse_date_time(&day_key(first), &time("from_time"));
+        let end = parse_date_time(&day_key(last), &time("to_time"));
+
+        let (Some(start), Some(end)) = (start, end) else {
+            self.date_errors.insert("to", tr!("Invalid time"));
+            return None;
+        };
+        // make the end exclusive
+        let end = end + 60;
+        if start >= end {
+            self.date_errors
+                .insert("to", tr!("The end must be after the start"));
+            return None;
+        }
+        Some((start, end))
+    }
+
+    fn set_range(&mut self, start: i64, end: i64) {
+        self.start_date = start as f64 * 1000.0;
+        self.end_date = end as f64 * 1000.0;
+        self.custom_range = true;
+    }
+
+    fn main_menu(&self, ctx: &Context<Self>) -> Menu {
+        let link = ctx.link();
+        let mut menu = Menu::new()
+            .with_item(
+                MenuItem::new(tr!("Language"))
+                    .icon_class("fa fa-language")
+                    .on_select(link.callback(|_| Msg::ShowLanguageSelect)),
+            )
+            .with_item(
+                MenuItem::new(tr!("Group Similar Mails"))
+                    .icon_class(if self.settings.grouped {
+                        "fa fa-check-square-o"
+                    } else {
+                        "fa fa-square-o"
+                    })
+                    .on_select(link.callback(|_| Msg::ToggleGrouped)),
+            )
+            .with_item(
+                MenuItem::new(match self.sort {
+                    ListSort::Newest => tr!("Show Oldest First"),
+                    ListSort::Oldest => tr!("Show Newest First"),
+                })
+                .icon_class("fa fa-sort")
+                .on_select(link.callback(|_| Msg::ToggleSort)),
+            )
+            .with_item(
+                MenuItem::new(tr!("Statistics"))
+                    .icon_class("fa fa-bar-chart")
+                    .on_select(link.callback(|_| Msg::ShowStatistics)),
+            )
+            .with_item(
+                MenuItem::new(tr!("Welcomelist & Blocklist"))
+                    .icon_class("fa fa-list")
+                    .on_select(link.callback(|_| Msg::ShowSenderLists)),
+            )
+            .with_item(
+                MenuItem::new(tr!("Settings"))
+                    .icon_class("fa fa-cog")
+                    .on_select(link.callback(|_| Msg::ShowSettings)),
+            )
+            .with_item(
+                MenuItem::new(tr!("Switch to Desktop View"))
+                    .icon_class("fa fa-desktop")
+                    .on_select(link.callback(|_| Msg::SwitchToDesktop)),
+            )
+            .with_item(
+                MenuItem::new(tr!("About"))
+                    .icon_class("fa fa-question-circle")
+                    .on_select(link.callback(|_| Msg::ShowAbout)),
+            )
+            .with_separator();
+        // links from spam reports log in for a limited time only
+        if let Some(remaining) = session_remaining() {
+            menu.add_item(
+                MenuItem::new(tr!("Link valid for {0}", format_remaining(remaining)))
+                    .icon_class("fa fa-clock-o")
+                    .disabled(true),
+            );
+        }
+        menu.with_item(
+            MenuItem::new(tr!("Logout"))
+                .icon_class("fa fa-sign-out")
+                .on_select(link.callback(|_| Msg::Logout)),
+        )
+    }
+
+    fn settings_panel(&self, ctx: &Context<Self>) -> Html {
+        let id = get_unique_element_id();
+        let mut notifications = Checkbox::new()
+            .label_id(id.clone())
+            .checked(self.settings.notifications)
+            .on_change(ctx.link().callback(Msg::Notifications));
+        if !notifications_supported() {
+            notifications = notifications.disabled(true);
+        }
+
+        let time_zone_id = get_unique_element_id();
+        let current = self.settings.time_zone.clone().unwrap_or_default();
+        let time_zone = html! {
+            <select
+                class="pwt-input"
+                aria-labelledby={time_zone_id.clone()}
+                onchange={ctx.link().callback(|event: Event| {
+                    Msg::TimeZone(event.target_unchecked_into::<HtmlSelectElement>().value())
+                })}
+            >
+                <option value="" selected={current.is_empty()}>{tr!("Device time zone")}</option>
+                { for time_zones().into_iter().map(|zone| html! {
+                    <option value={zone.clone()} selected={zone == current}>{zone}</option>
+                })}
+            </select>
+        };
+
+        let server_timeout = ctx
+            .link()
+            .context::<ServerConfig>(Callback::noop())
+            .map_or(0, |(config, _)| config.IdleTimeout);
+        let timeout_label = |minutes: u32| match minutes {
+            0 => tr!("Never"),
+            minutes => tr!("After {0} minutes", minutes),
+        };
+        let idle_timeout_id = get_unique_element_id();
+        // a timeout set by the server cannot be changed
+        let idle_timeout = if server_timeout > 0 {
+            html! {
+                <select
+                    class="pwt-input"
+                    aria-labelledby={idle_timeout_id.clone()}
+                    disabled=true
+                >
+                    <option selected=true>
+                        {tr!("{0} (set by the server)", timeout_label(server_timeout))}
+                    </option>
+                </select>
+            }
+        } else {
+            let current = self.settings.idle_timeout.unwrap_or(0);
+            html! {
+                <select
+                    class="pwt-input"
+                    aria-labelledby={idle_timeout_id.clone()}
+                    onchange={ctx.link().callback(|event: Event| {
+                        Msg::IdleTimeout(event.target_unchecked_into::<HtmlSelectElement>().value())
+                    })}
+                >
+                    { for IDLE_TIMEOUT_CHOICES.into_iter().map(|minutes| html! {
+                        <option value={minutes.to_string()} selected={current == minutes}>
+                            {timeout_label(minutes)}
+                        </option>
+                    })}
+                </select>
+            }
+        };
+
+        let reduced_motion_id = get_unique_element_id();
+        let reduced_motion = Checkbox::new()
+            .label_id(reduced_motion_id.clone())
+            .checked(self.settings.reduced_motion)
+            .on_change(ctx.link().callback(Msg::ReducedMotion));
+
+        Column::new()
+            .padding(2)
+            .gap(1)
+            .with_child(
+                Row::new()
+                    .class(AlignItems::Center)
+                    .gap(1)
+                    .with_child(FieldLabel::new(tr!("Notify about new mail")).id(id))
+                    .with_flex_spacer()
+                    .with_child(notifications),
+            )
+            .with_child(
+                Row::new()
+                    .class(AlignItems::Center)
+                    .gap(1)
+                    .with_child(
+                        FieldLabel::new(tr!("Reduce motion (no swipe actions)"))
+                            .id(reduced_motion_id),
+                    )
+                    .with_flex_spacer()
+                    .with_child(reduced_motion),
+            )
+            .with_child(
+                Row::new()
+                    .class(AlignItems::Center)
+                    .gap(1)
+                    .with_child(FieldLabel::new(tr!("Time zone")).id(time_zone_id))
+                    .with_flex_spacer()
+                    .with_child(time_zone),
+            )
+            .with_child(
+                Row::new()
+                    .class(AlignItems::Center)
+                    .gap(1)
+                    .with_child(FieldLabel::new(tr!("Log out when idle")).id(idle_timeout_id))
+                    .with_flex_spacer()
+                    .with_child(idle_timeout),
+            )
+            .with_child(Row::new().class(JustifyContent::FlexEnd).with_child(
+                Button::new(tr!("OK")).on_activate(ctx.link().callback(|_| Msg::CloseDialog)),
+            ))
+            .into()
+    }
 }
 
 impl Component for PmgPageSpamList {
@@ -142,15 +739,7 @@ impl Component for PmgPageSpamList {
     type Properties = PageSpamList;
 
     fn create(ctx: &Context<Self>) -> Self {
-        let start_date = js_sys::Date::new_0();
-        start_date.set_hours(0);
-        start_date.set_minutes(0);
-        start_date.set_seconds(0);
-        start_date.set_milliseconds(0);
-
-        let mut start_date = start_date.get_time();
-        let end_date = start_date + 24.0 * 3600000.0;
-        start_date = end_date - 7.0 * 24.0 * 3600000.0;
+        let (start, end) = DatePreset::Last7Days.range();
 
         let link = ctx.link().clone();
         spawn_local(async move {
@@ -158,18 +747,44 @@ impl Component for PmgPageSpamList {
             link.send_message(Msg::SubscriptionResult(result));
         });
 
-        Self {
+        let mut me = Self {
             state: ViewState::Normal,
-            start_date,
-            end_date,
+            start_date: start as f64 * 1000.0,
+            end_date: end as f64 * 1000.0,
+            custom_range: false,
+            with_time: false,
+            picked: None,
+            date_errors: HashMap::new(),
             form_context: FormContext::new(),
             subscription_result: None,
+            settings: Settings::load(),
+            sender: None,
+            sort: ListSort::default(),
+            address: None,
+            history_guard: None,
+            _location_listener: ctx
+                .link()
+                .add_location_listener(ctx.link().callback(Msg::LocationChanged)),
+            _tab_subscription: TabSubscription::new(ctx.link().batch_callback(|event| {
+                matches!(event, TabEvent::Settings).then_some(Msg::SettingsChanged)
+            })),
+            list_overlay: false,
+        };
+        if let Some(location) = ctx.link().location() {
+            me.apply_query(&location);
         }
+        me
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Msg::ShowDialog => {
+                let start = (self.start_date / 1000.0) as i64;
+                let end = (self.end_date / 1000.0) as i64;
+                // the whole quarantine is no range to show in the calendar
+                self.picked = (start > 0).then(|| (day_start(start), day_start(end - 1)));
+                self.date_errors.clear();
+                self.form_context = FormContext::new();
                 self.state = ViewState::ShowDialog;
                 true
             }
@@ -182,19 +797,54 @@ impl Component for PmgPageSpamList {
                 true
             }
             Msg::ApplyDate => {
+                if let Some((start, end)) = self.read_date_range() {
+                    self.set_range(start, end);
+                    self.state = ViewState::Normal;
+                    self.push_query(ctx);
+                }
+                true
+            }
+            Msg::PickRange(range) => {
+                self.picked = Some(range);
+                self.date_errors.remove("from");
+                true
+            }
+            Msg::DatePreset(preset) => {
+                let (start, end) = preset.range();
+                self.with_time = preset == DatePreset::Last24Hours;
+                self.set_range(start, end);
                 self.state = ViewState::Normal;
-
-                let start = self.form_context.read().get_field_value("from").unwrap();
-                self.start_date = Date::parse(start.as_str().unwrap());
-                let end = self.form_context.read().get_field_value("to").unwrap();
-                self.end_date = Date::parse(end.as_str().unwrap());
-
+                self.push_query(ctx);
+                true
+            }
+            Msg::WithTime(with_time) => {
+                self.with_time = with_time;
+                true
+            }
+            Msg::ResetRange => {
+                let (start, end) = DatePreset::Last7Days.range();
+                self.set_range(start, end);
+                self.custom_range = false;
+                self.with_time = false;
+                self.push_query(ctx);
                 true
             }
             Msg::Preview(id) => {
                 //log::info!("Preview {id}");
+                let props = ctx.props();
                 let navigator = ctx.link().navigator().unwrap();
-                navigator.push(&Route::ViewMail { id: id.clone() });
+                let route = Route::ViewMail { id: id.clone() };
+                // keep the list state, so going back restores it
+                let query = self.query();
+                // switching between previews should not pile up history entries
+                let result = if props.two_pane && props.selected.is_some() {
+                    navigator.replace_with_query(&route, &query)
+                } else {
+                    navigator.push_with_query(&route, &query)
+                };
+                if let Err(err) = result {
+                    log::error!("could not open mail: {err}");
+                }
                 true
             }
             Msg::SubscriptionResult(result) => {
@@ -229,19 +879,144 @@ impl Component for PmgPageSpamList {
                 self.state = ViewState::ShowAbout;
                 true
             }
+            Msg::ShowSettings => {
+                self.state = ViewState::ShowSettings;
+                true
+            }
+            Msg::Notifications(true) => {
+                // only enable once the browser actually lets us notify
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    let granted = request_permission().await;
+                    link.send_message(Msg::NotificationPermission(granted));
+                });
+                false
+            }
+            Msg::Notifications(false) => {
+                self.settings.notifications = false;
+                self.settings.store();
+                clear_badge();
+                true
+            }
+            Msg::ShowSenderLists => {
+                ctx.link().navigator().unwrap().push(&Route::SenderLists);
+                false
+            }
+            Msg::ShowStatistics => {
+                let query = ListQuery {
+                    start: Some((self.start_date / 1000.0) as u64),
+                    end: Some((self.end_date / 1000.0) as u64),
+                    address: self.address.clone(),
+                    ..Default::default()
+                };
+                let navigator = ctx.link().navigator().unwrap();
+                if let Err(err) = navigator.push_with_query(&Route::Statistics, &query) {
+                    log::error!("could not open statistics: {err}");
+                }
+                false
+            }
+            Msg::ClearSender => {
+                self.sender = None;
+                self.push_query(ctx);
+                true
+            }
+            Msg::ClearAddress => {
+                self.address = None;
+                self.push_query(ctx);
+                true
+            }
+            Msg::LocationChanged(location) => {
+                self.apply_query(&location);
+                true
+            }
+            Msg::ToggleSort => {
+                self.sort = match self.sort {
+                    ListSort::Newest => ListSort::Oldest,
+                    ListSort::Oldest => ListSort::Newest,
+                };
+                self.push_query(ctx);
+                true
+            }
+            Msg::ToggleGrouped => {
+                self.settings.grouped = !self.settings.grouped;
+                self.settings.store();
+                true
+            }
+            Msg::TimeZone(time_zone) => {
+                self.settings.time_zone = (!time_zone.is_empty()).then_some(time_zone);
+                self.settings.store();
+                true
+            }
+            Msg::SettingsChanged => {
+                self.settings = Settings::load();
+                true
+            }
+            Msg::ListOverlay(open) => {
+                self.list_overlay = open;
+                true
+            }
+            Msg::IdleTimeout(minutes) => {
+                self.settings.idle_timeout = minutes.parse().ok();
+                self.settings.store();
+                true
+            }
+            Msg::ReducedMotion(reduced_motion) => {
+                self.settings.reduced_motion = reduced_motion;
+                self.settings.store();
+                true
+            }
+            Msg::NotificationPermission(granted) => {
+                if granted {
+                    self.settings.notifications = true;
+                    self.settings.store();
+                    notifications::reset_last_notified();
+                    spawn_local(async move {
+                        if let Err(err) = notifications::poll().await {
+                            log::error!("could not poll quarantine: {err}");
+                        }
+                    });
+                } else {
+                    ctx.link().show_snackbar(
+                        SnackBar::new().message(tr!("Notifications are blocked by the browser")),
+                    );
+                }
+                true
+            }
             Msg::Logout => {
-                proxmox_yew_comp::http_clear_auth();
+                log_out();
                 true
             }
         }
     }
 
+    fn rendered(&mut self, ctx: &Context<Self>, _first_render: bool) {
+        let open = !matches!(self.state, ViewState::Normal);
+        sync_history_guard(&mut self.history_guard, open, || {
+            ctx.link().callback(|_| Msg::CloseDialog)
+        });
+    }
+
     fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
         let link = ctx.link();
         let content = SpamList::new()
             .starttime((self.start_date / 1000.0) as u64)
             .endtime((self.end_date / 1000.0) as u64)
-            .on_preview(link.callback(Msg::Preview));
+            .pmail(self.address.clone())
+            .sender(self.sender.clone())
+            .grouped(self.settings.grouped)
+            .sort(self.sort)
+            .time_zone(self.settings.time_zone.clone())
+            .selected(props.selected.clone())
+            // with a single pane the selected mail covers the list, and the
+            // dialogs of the page take the keyboard while open
+            .keyboard(
+                (props.two_pane || props.selected.is_none())
+                    && matches!(self.state, ViewState::Normal),
+            )
+            .reduced_motion(self.settings.reduced_motion || prefers_reduced_motion())
+            .on_preview(link.callback(Msg::Preview))
+            .on_overlay(link.callback(Msg::ListOverlay));
 
         let dialog = match self.state {
             ViewState::Normal => None,
@@ -295,6 +1070,11 @@ impl Component for PmgPageSpamList {
                     )
                     .on_close(link.callback(|_| Msg::CloseDialog)),
             ),
+            ViewState::ShowSettings => Some(
+                Dialog::new(tr!("Settings"))
+                    .with_child(self.settings_panel(ctx))
+                    .on_close(link.callback(|_| Msg::CloseDialog)),
+            ),
         };
 
         let fab = Fab::new("fa fa-calendar").on_activate(link.callback(|_| Msg::ShowDialog));
@@ -316,63 +1096,67 @@ impl Component for PmgPageSpamList {
             ),
         };
 
-        Scaffold::new()
-            .application_bar(
-                ApplicationBar::new()
-                    .leading(
-                        Image::new("/mobile/images/proxmox_logo.svg")
-                            .dark_mode_src("/mobile/images/proxmox_logo_white.svg")
-                            .height(30)
-                            .class("pwt-navbar-brand"),
-                    )
-                    .title("Mail")
-                    .with_action(
-                        Row::new()
-                            .gap(1)
-                            .with_child(ThemeModeSelector::new())
-                            .with_child(
-                                MenuButton::new("")
-                                    .class("circle")
-                                    .icon_class("fa fa-bars")
-                                    .menu(
-                                        Menu::new()
-                                            .with_item(
-                                                MenuItem::new(tr!("Language"))
-                                                    .icon_class("fa fa-language")
-                                                    .on_select(
-                                                        link.callback(|_| Msg::ShowLanguageSelect),
-                                                    ),
-                                            )
-                                            .with_item(
-                                                MenuItem::new(tr!("Switch to Desktop View"))
-                                                    .icon_class("fa fa-desktop")
-                                                    .on_select(
-                                                        link.callback(|_| Msg::SwitchToDesktop),
-                                                    ),
-                                            )
-                                            .with_item(
-                                                MenuItem::new(tr!("About"))
-                                                    .icon_class("fa fa-question-circle")
-                                                    .on_select(link.callback(|_| Msg::ShowAbout)),
-                                            )
-                                            .with_separator()
-                                            .with_item(
-                                                MenuItem::new(tr!("Logout"))
-                                                    .icon_class("fa fa-sign-out")
-                                                    .on_select(link.callback(|_| Msg::Logout)),
-                                            ),
-                                    ),
-                            ),
-                    ),
+        let config = ctx
+            .link()
+            .context::<ServerConfig>(Callback::noop())
+            .map(|(config, _)| config)
+            .unwrap_or_default();
+        let app_bar = ApplicationBar::new()
+            .leading(
+                Image::new(config.image_url("proxmox_logo.svg"))
+                    .dark_mode_src(config.image_url("proxmox_logo_white.svg"))
+                    .height(30)
+                    .class("pwt-navbar-brand"),
             )
-            .body(
-                Column::new()
+            .title("Mail")
+            .with_action(
+                Row::new()
+                    .gap(1)
+                    .with_child(ThemeModeSelector::new())
+                    .with_child(
+                        MenuButton::new("")
+                            .class("circle")
+                            .icon_class("fa fa-bars")
+                            .menu(self.main_menu(ctx)),
+                    ),
+            );
+
+        let list = Column::new()
+            .class(FlexFit)
+            .with_optional_child(self.address_chip(ctx))
+            .with_optional_child(self.range_chip(ctx))
+            .with_optional_child(self.sender_chip(ctx))
+            .with_child(content)
+            .with_optional_child(dialog)
+            .with_flex_spacer()
+            .with_optional_child(sub_notice);
+
+        let body: Html = if props.two_pane {
+            let preview: Html = match &props.selected {
+                // the dialogs of the page and the list take the keyboard
+                Some(id) => PageMailView::new(id.clone())
+                    .keyboard(matches!(self.state, ViewState::Normal) && !self.list_overlay)
+                    .into(),
+                None => Row::new()
                     .class(FlexFit)
-                    .with_child(content)
-                    .with_optional_child(dialog)
-                    .with_flex_spacer()
-                    .with_optional_child(sub_notice),
-            )
+                    .class(AlignItems::Center)
+                    .class(JustifyContent::Center)
+                    .padding(2)
+                    .with_child(tr!("Select a mail to preview it here."))
+                    .into(),
+            };
+            Row::new()
+                .class(FlexFit)
+                .with_child(list.width("40%").min_width("20rem").border_end(true))
+                .with_child(Column::new().class(FlexFit).with_child(preview))
+                .into()
+        } else {
+            list.into()
+        };
+
+        Scaffold::new()
+            .application_bar(app_bar)
+            .body(body)
             .favorite_action_button(fab)
             .into()
     }
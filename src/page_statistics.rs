@@ -0,0 +1,401 @@
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+use anyhow::Error;
+
+use yew::virtual_dom::{VComp, VNode};
+use yew_router::scope_ext::RouterScopeExt;
+
+use pwt::css::{AlignItems, ColorScheme, FlexFit, Opacity, Overflow};
+use pwt::prelude::*;
+use pwt::touch::{ApplicationBar, Scaffold};
+use pwt::widget::{error_message, Column, Container, Progress, Row};
+
+use crate::date_format::{day_start, format_date, next_day_start};
+use crate::page_spam_list::ListQuery;
+use crate::spam_list::{button_keydown, load_mails, sender_address, sender_domain, MailInfo};
+use crate::Route;
+
+// number of entries in the top sender and domain lists
+const TOP_COUNT: usize = 10;
+
+// spam score buckets, the last one is open ended
+const SCORE_BUCKETS: &[i64] = &[0, 5, 10, 15, 20, 30];
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct PageStatistics {}
+
+impl PageStatistics {
+    pub fn new() -> Self {
+        yew::props!(Self {})
+    }
+}
+
+impl Default for PageStatistics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub enum Msg {
+    LoadResult(Result<Vec<MailInfo>, Error>),
+    ShowDay(i64), // day start
+    ShowSender(String),
+}
+
+struct Statistics {
+    total: usize,
+    unseen: usize,
+    per_day: BTreeMap<i64, usize>,
+    // (lower bound, count)
+    scores: Vec<(i64, usize)>,
+    top_senders: Vec<(String, usize)>,
+    top_domains: Vec<(String, usize)>,
+}
+
+impl Statistics {
+    fn new(mails: &[MailInfo]) -> Self {
+        let mut per_day = BTreeMap::new();
+        let mut scores: Vec<(i64, usize)> = SCORE_BUCKETS.iter().map(|s| (*s, 0)).collect();
+        let mut senders: HashMap<String, usize> = HashMap::new();
+        let mut domains: HashMap<String, usize> = HashMap::new();
+
+        for mail in mails {
+            *per_day.entry(day_start(mail.time)).or_default() += 1;
+
+            let bucket = scores
+                .iter()
+                .rposition(|(lower, _)| mail.spamlevel >= *lower)
+                .unwrap_or(0);
+            scores[bucket].1 += 1;
+
+            let address = sender_address(&mail.from);
+            *domains
+                .entry(sender_domain(&address).to_string())
+                .or_default() += 1;
+            *senders.entry(address).or_default() += 1;
+        }
+
+        Self {
+            total: mails.len(),
+            unseen: mails.iter().filter(|mail| !mail.seen).count(),
+            per_day,
+            scores,
+            top_senders: top_entries(senders),
+            top_domains: top_entries(domains),
+        }
+    }
+}
+
+fn top_entries(counts: HashMap<String, usize>) -> Vec<(String, usize)> {
+    let mut list: Vec<_> = counts.into_iter().collect();
+    list.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then_with(|| a_name.cmp(b_name)));
+    list.truncate(TOP_COUNT);
+    list
+}
+
+fn percentage(count: usize, max: usize) -> f64 {
+    if max == 0 {
+        0.0
+    } else {
+        100.0 * count as f64 / max as f64
+    }
+}
+
+fn section(title: String, child: impl Into<Html>) -> Column {
+    Column::new()
+        .gap(1)
+        .padding(2)
+        .with_child(
+            Container::new()
+                .class("pwt-font-title-medium")
+                .attribute("role", "heading")
+                .attribute("aria-level", "2")
+                .with_child(title),
+        )
+        .with_child(child)
+}
+
+// a labeled horizontal bar, optionally tappable
+fn bar_row(label: String, count: usize, max: usize, on_activate: Option<Callback<()>>) -> Row {
+    let bar = Container::new()
+        .class(ColorScheme::Primary)
+        .style("width", format!("{:.1}%", percentage(count, max)))
+        .style("height", "0.75rem")
+        .style("border-radius", "0.25rem");
+
+    let mut row = Row::new()
+        .class(AlignItems::Center)
+        .gap(1)
+        .with_child(
+            Container::new()
+                .class("pwt-text-truncate")
+                .width("40%")
+                .attribute("title", label.clone())
+                .with_child(label),
+        )
+        .with_child(Container::new().class(FlexFit).with_child(bar))
+        .with_child(
+            Container::new()
+                .class("pwt-white-space-nowrap")
+                .with_child(count.to_string()),
+        );
+
+    if let Some(on_activate) = on_activate {
+        row = row
+            .style("cursor", "pointer")
+            .attribute("role", "button")
+            .attribute("tabindex", "0")
+            .onkeydown(button_keydown(on_activate.clone()))
+            .onclick(move |_| on_activate.emit(()));
+    }
+    row
+}
+
+pub struct PmgPageStatistics {
+    query: ListQuery,
+    data: Option<Result<Statistics, Error>>,
+}
+
+impl PmgPageStatistics {
+    fn show_list(ctx: &Context<Self>, query: ListQuery) {
+        let navigator = ctx.link().navigator().unwrap();
+        if let Err(err) = navigator.push_with_query(&Route::SpamList, &query) {
+            log::error!("could not show filtered list: {err}");
+        }
+    }
+
+    // every day of the selected range, or of the mails if the range is open
+    fn days(&self, stats: &Statistics) -> Vec<(i64, usize)> {
+        let first = self
+            .query
+            .start
+            .map(|start| day_start(start as i64))
+            .or_else(|| stats.per_day.keys().next().copied());
+        // the end is exclusive
+        let last = self
+            .query
+            .end
+            .map(|end| day_start(end as i64 - 1))
+            .or_else(|| stats.per_day.keys().last().copied());
+        let (Some(first), Some(last)) = (first, last) else {
+            return Vec::new();
+        };
+        let mut days = Vec::new();
+        let mut day = first;
+        while day <= last {
+            days.push((day, stats.per_day.get(&day).copied().unwrap_or(0)));
+            day = next_day_start(day);
+        }
+        days
+    }
+
+    fn per_day_view(&self, ctx: &Context<Self>, stats: &Statistics) -> Html {
+        let days = self.days(stats);
+        let max = days.iter().map(|(_, count)| *count).max().unwrap_or(0);
+
+        let mut chart = Row::new()
+            .style("align-items", "flex-end")
+            .gap(1)
+            .style("height", "8rem")
+            .class(Overflow::Auto);
+        for (day, count) in &days {
+            let label = tr!("{0}: {1} mails", format_date(*day), count);
+            let day = *day;
+            let on_activate = ctx.link().callback(move |()| Msg::ShowDay(day));
+            chart.add_child(
+                Container::new()
+                    .class(ColorScheme::Primary)
+                    .style("flex", "1 0 0.75rem")
+                    .style("height", format!("{:.1}%", percentage(*count, max)))
+                    // empty days only show as a line on the axis
+                    .style("min-height", "2px")
+                    .style("cursor", "pointer")
+                    .attribute("role", "button")
+                    .attribute("tabindex", "0")
+                    .attribute("title", label.clone())
+                    .attribute("aria-label", label)
+                    .onkeydown(button_keydown(on_activate.clone()))
+                    .onclick(move |_| on_activate.emit(())),
+            );
+        }
+
+        let (first, last) = match (days.first(), days.last()) {
+            (Some((first, _)), Some((last, _))) => (format_date(*first), format_date(*last)),
+            _ => (String::new(), String::new()),
+        };
+
+        Column::new()
+            .gap(1)
+            .with_child(chart)
+            .with_child(
+                Row::new()
+                    .class("pwt-font-label-small")
+                    .class(Opacity::Half)
+                    .with_child(first)
+                    .with_flex_spacer()
+                    .with_child(last),
+            )
+            .into()
+    }
+
+    fn sender_list(&self, ctx: &Context<Self>, list: &[(String, usize)], domains: bool) -> Html {
+        let max = list.first().map(|(_, count)| *count).unwrap_or(0);
+        let mut column = Column::new().gap(1);
+        for (name, count) in list {
+            let filter = if domains {
+                format!("@{name}")
+            } else {
+                name.clone()
+            };
+            column.add_child(bar_row(
+                name.clone(),
+                *count,
+                max,
+                Some(
+                    ctx.link()
+                        .callback(move |()| Msg::ShowSender(filter.clone())),
+                ),
+            ));
+        }
+        column.into()
+    }
+
+    fn statistics_view(&self, ctx: &Context<Self>, stats: &Statistics) -> Html {
+        let max_score = stats
+            .scores
+            .iter()
+            .map(|(_, count)| *count)
+            .max()
+            .unwrap_or(0);
+        let mut scores = Column::new().gap(1);
+        for (pos, (lower, count)) in stats.scores.iter().enumerate() {
+            let label = match stats.scores.get(pos + 1) {
+                Some((upper, _)) => format!("{lower} – {}", upper - 1),
+                None => format!("≥ {lower}"),
+            };
+            scores.add_child(bar_row(label, *count, max_score, None));
+        }
+
+        let seen = stats.total - stats.unseen;
+        let seen_share = Column::new()
+            .gap(1)
+            .with_child(
+                Row::new()
+                    .style("height", "0.75rem")
+                    .style("border-radius", "0.25rem")
+                    .style("overflow", "hidden")
+                    .with_child(
+                        Container::new()
+                            .class(ColorScheme::Primary)
+                            .style("width", format!("{:.1}%", percentage(seen, stats.total))),
+                    )
+                    .with_child(
+                        Container::new()
+                            .class(FlexFit)
+                            .class(ColorScheme::SecondaryContainer),
+                    ),
+            )
+            .with_child(
+                Row::new()
+                    .class("pwt-font-label-medium")
+                    .with_child(tr!("Seen: {0}", seen))
+                    .with_flex_spacer()
+                    .with_child(tr!("Unseen: {0}", stats.unseen)),
+            );
+
+        Column::new()
+            .class(FlexFit)
+            .class(Overflow::Auto)
+            .with_child(
+                Container::new()
+                    .padding(2)
+                    .class("pwt-font-headline-small")
+                    .with_child(tr!("{0} quarantined mails", stats.total)),
+            )
+            .with_child(section(tr!("Mails per Day"), self.per_day_view(ctx, stats)))
+            .with_child(section(tr!("Spam Score"), scores))
+            .with_child(section(
+                tr!("Top Senders"),
+                self.sender_list(ctx, &stats.top_senders, false),
+            ))
+            .with_child(section(
+                tr!("Top Sender Domains"),
+                self.sender_list(ctx, &stats.top_domains, true),
+            ))
+            .with_child(section(tr!("Seen and Unseen"), seen_share))
+            .into()
+    }
+}
+
+impl Component for PmgPageStatistics {
+    type Message = Msg;
+    type Properties = PageStatistics;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let query = ctx
+            .link()
+            .location()
+            .and_then(|location| location.query::<ListQuery>().ok())
+            .unwrap_or_default();
+
+        let link = ctx.link().clone();
+        let (start, end, address) = (query.start, query.end, query.address.clone());
+        wasm_bindgen_futures::spawn_local(async move {
+            link.send_message(Msg::LoadResult(load_mails(start, end, address).await));
+        });
+
+        Self { query, data: None }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::LoadResult(result) => {
+                self.data = Some(result.map(|mails| Statistics::new(&mails)));
+                true
+            }
+            Msg::ShowDay(day) => {
+                let query = ListQuery {
+                    start: Some(day as u64),
+                    end: Some(next_day_start(day) as u64),
+                    address: self.query.address.clone(),
+                    ..Default::default()
+                };
+                Self::show_list(ctx, query);
+                false
+            }
+            Msg::ShowSender(sender) => {
+                let query = ListQuery {
+                    sender: Some(sender),
+                    ..self.query.clone()
+                };
+                Self::show_list(ctx, query);
+                false
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let body: Html = match &self.data {
+            Some(Ok(stats)) if stats.total > 0 => self.statistics_view(ctx, stats),
+            Some(Ok(_)) => Row::new()
+                .padding(2)
+                .with_child(tr!("No data in database"))
+                .into(),
+            Some(Err(err)) => error_message(&err.to_string()).into(),
+            None => Progress::new().into(),
+        };
+
+        Scaffold::new()
+            .application_bar(ApplicationBar::new().title(tr!("Statistics")))
+            .body(body)
+            .into()
+    }
+}
+
+impl From<PageStatistics> for VNode {
+    fn from(val: PageStatistics) -> Self {
+        let comp = VComp::new::<PmgPageStatistics>(Rc::new(val), None);
+        VNode::from(comp)
+    }
+}
@@ -0,0 +1,128 @@
+use anyhow::Error;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::Deserialize;
+use serde_json::json;
+
+use pwt::prelude::*;
+
+use crate::session::{http_delete, http_get, http_post};
+use crate::spam_list::{sender_address, sender_domain, MailInfo};
+use crate::MailAction;
+
+/// The user's personal sender lists.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum SenderList {
+    Welcomelist,
+    Blocklist,
+}
+
+impl SenderList {
+    fn path(&self) -> &'static str {
+        match self {
+            SenderList::Welcomelist => "/quarantine/welcomelist",
+            SenderList::Blocklist => "/quarantine/blocklist",
+        }
+    }
+
+    /// The list a welcomelist or blocklist mail action adds the sender to.
+    pub(crate) fn from_action(action: MailAction) -> Option<Self> {
+        match action {
+            MailAction::Welcomelist => Some(SenderList::Welcomelist),
+            MailAction::Blocklist => Some(SenderList::Blocklist),
+            _ => None,
+        }
+    }
+
+    /// What to do with quarantined mail from senders on this list.
+    pub(crate) fn mail_action(&self) -> MailAction {
+        match self {
+            SenderList::Welcomelist => MailAction::Deliver,
+            SenderList::Blocklist => MailAction::Delete,
+        }
+    }
+
+    pub(crate) fn label(&self) -> String {
+        match self {
+            SenderList::Welcomelist => tr!("Welcomelist"),
+            SenderList::Blocklist => tr!("Blocklist"),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SenderListEntry {
+    address: String,
+}
+
+/// Loads all addresses and patterns on the given list.
+pub(crate) async fn load_sender_list(list: SenderList) -> Result<Vec<String>, Error> {
+    let entries: Vec<SenderListEntry> = http_get(list.path(), None).await?;
+    Ok(entries.into_iter().map(|entry| entry.address).collect())
+}
+
+pub(crate) async fn add_to_sender_list(list: SenderList, address: &str) -> Result<(), Error> {
+    http_post::<()>(list.path(), Some(json!({ "address": address }))).await
+}
+
+/// Adds several entries with a single request, the API accepts a comma
+/// separated list of addresses.
+pub(crate) async fn add_entries_to_sender_list(
+    list: SenderList,
+    entries: &[String],
+) -> Result<(), Error> {
+    add_to_sender_list(list, &entries.join(",")).await
+}
+
+pub(crate) async fn remove_from_sender_list(list: SenderList, address: &str) -> Result<(), Error> {
+    let address = utf8_percent_encode(address, NON_ALPHANUMERIC);
+    http_delete(format!("{}/{address}", list.path()), None).await
+}
+
+/// The list entry covering a whole domain.
+pub(crate) fn domain_pattern(address: &str) -> String {
+    format!("*@{}", sender_domain(address))
+}
+
+/// Basic sanity check of an address or pattern before sending it to the
+/// server, which does the real validation.
+pub(crate) fn valid_entry(entry: &str) -> bool {
+    !entry.is_empty()
+        && !entry.contains(|c: char| c.is_whitespace() || c == ',' || c == ';')
+        && (entry.contains('@') || entry.contains('.'))
+}
+
+/// Checks an address against a list entry, where `*` matches any text and `?`
+/// a single character.
+pub(crate) fn entry_matches(entry: &str, address: &str) -> bool {
+    let entry: Vec<char> = entry.to_lowercase().chars().collect();
+    let address: Vec<char> = address.to_lowercase().chars().collect();
+
+    // iterative wildcard matching, backtracking to the last `*` on mismatch
+    let (mut e, mut a) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while a < address.len() {
+        if e < entry.len() && (entry[e] == '?' || entry[e] == address[a]) {
+            e += 1;
+            a += 1;
+        } else if e < entry.len() && entry[e] == '*' {
+            star = Some((e, a));
+            e += 1;
+        } else if let Some((star_e, star_a)) = star {
+            e = star_e + 1;
+            a = star_a + 1;
+            star = Some((star_e, star_a + 1));
+        } else {
+            return false;
+        }
+    }
+    entry[e..].iter().all(|c| *c == '*')
+}
+
+/// Ids of the mails, except `skip_id`, whose sender matches the entry.
+pub(crate) fn matching_mails(mails: &[MailInfo], entry: &str, skip_id: &str) -> Vec<String> {
+    mails
+        .iter()
+        .filter(|mail| mail.id != skip_id && entry_matches(entry, &sender_address(&mail.from)))
+        .map(|mail| mail.id.clone())
+        .collect()
+}
@@ -0,0 +1,325 @@
+use std::rc::Rc;
+
+use anyhow::Error;
+
+use yew::context::ContextHandle;
+use yew::html::IntoEventCallback;
+use yew::virtual_dom::{VComp, VNode};
+
+use pwt::css::{AlignItems, JustifyContent, Opacity};
+use pwt::prelude::*;
+use pwt::state::SharedStateObserver;
+use pwt::touch::{SideDialog, SideDialogLocation, SnackBar, SnackBarContextExt};
+use pwt::widget::{error_message, Button, Column, Container, Fa, Progress, Row};
+
+use crate::a11y::announce;
+use crate::sender_lists::{
+    add_to_sender_list, domain_pattern, load_sender_list, remove_from_sender_list, SenderList,
+};
+use crate::spam_list::{sender_address, sender_domain, sender_matches, MailInfo};
+use crate::LoadedMails;
+
+/// Bottom sheet with what is known about the sender of a mail: whether the
+/// address or its domain is on the user's welcomelist or blocklist and how
+/// many mails from it the list currently shows.
+#[derive(Clone, PartialEq, Properties)]
+pub struct SenderSheet {
+    /// The `From` header of the mail.
+    from: String,
+    #[prop_or_default]
+    on_close: Option<Callback<()>>,
+}
+
+impl SenderSheet {
+    pub fn new(from: impl Into<String>) -> Self {
+        yew::props!(Self { from: from.into() })
+    }
+
+    pub fn on_close(mut self, cb: impl IntoEventCallback<()>) -> Self {
+        self.on_close = cb.into_event_callback();
+        self
+    }
+}
+
+pub struct ListStatus {
+    welcomelist: Vec<String>,
+    blocklist: Vec<String>,
+}
+
+impl ListStatus {
+    fn entries(&self, list: SenderList) -> &[String] {
+        match list {
+            SenderList::Welcomelist => &self.welcomelist,
+            SenderList::Blocklist => &self.blocklist,
+        }
+    }
+
+    fn contains(&self, list: SenderList, entry: &str) -> bool {
+        self.entries(list)
+            .iter()
+            .any(|listed| listed.eq_ignore_ascii_case(entry))
+    }
+}
+
+async fn load_status() -> Result<ListStatus, Error> {
+    Ok(ListStatus {
+        welcomelist: load_sender_list(SenderList::Welcomelist).await?,
+        blocklist: load_sender_list(SenderList::Blocklist).await?,
+    })
+}
+
+pub enum Msg {
+    LoadResult(Result<ListStatus, Error>),
+    Add(SenderList, String),                 // entry
+    Remove(SenderList, String),              // entry
+    ActionResult(Result<(), Error>, String), // success message
+    LoadedChanged(LoadedMails),
+    MailsChanged,
+}
+
+pub struct PmgSenderSheet {
+    status: Option<Result<ListStatus, Error>>,
+    loaded: Option<LoadedMails>,
+    _loaded_handle: Option<ContextHandle<LoadedMails>>,
+    // re-renders the counts once the list reloaded
+    _mails_observer: Option<SharedStateObserver<Vec<MailInfo>>>,
+}
+
+impl PmgSenderSheet {
+    fn observe(&mut self, ctx: &Context<Self>, loaded: LoadedMails) {
+        self._mails_observer = Some(
+            loaded
+                .0
+                .add_listener(ctx.link().callback(|_| Msg::MailsChanged)),
+        );
+        self.loaded = Some(loaded);
+    }
+
+    fn load(ctx: &Context<Self>) {
+        let link = ctx.link().clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            link.send_message(Msg::LoadResult(load_status().await));
+        });
+    }
+
+    fn status_view(&self, address: &str, domain: &str) -> Html {
+        let status = match &self.status {
+            Some(Ok(status)) => status,
+            Some(Err(err)) => return error_message(&err.to_string()).into(),
+            None => return Progress::new().into(),
+        };
+
+        let pattern = domain_pattern(address);
+        let mut lines = Column::new().gap(1);
+        let mut listed = false;
+        for list in [SenderList::Welcomelist, SenderList::Blocklist] {
+            let icon = match list {
+                SenderList::Welcomelist => "check",
+                SenderList::Blocklist => "ban",
+            };
+            let text = match (
+                list,
+                status.contains(list, address),
+                status.contains(list, &pattern),
+            ) {
+                (_, false, false) => continue,
+                (SenderList::Welcomelist, true, _) => tr!("Address is on your welcomelist"),
+                (SenderList::Welcomelist, false, true) => {
+                    tr!("Domain {0} is on your welcomelist", domain)
+                }
+                (SenderList::Blocklist, true, _) => tr!("Address is on your blocklist"),
+                (SenderList::Blocklist, false, true) => {
+                    tr!("Domain {0} is on your blocklist", domain)
+                }
+            };
+            listed = true;
+            lines.add_child(
+                Row::new()
+                    .gap(1)
+                    .class(AlignItems::Center)
+                    .with_child(Fa::new(icon))
+                    .with_child(text),
+            );
+        }
+
+        if !listed {
+            return Container::new()
+                .class(Opacity::Half)
+                .with_child(tr!("Not on your welcomelist or blocklist"))
+                .into();
+        }
+        lines.into()
+    }
+
+    fn list_button(
+        &self,
+        ctx: &Context<Self>,
+        list: SenderList,
+        entry: String,
+        domain: bool,
+    ) -> Button {
+        let listed = matches!(&self.status, Some(Ok(status)) if status.contains(list, &entry));
+        let label = match (list, domain, listed) {
+            (SenderList::Welcomelist, false, false) => tr!("Welcomelist Address"),
+            (SenderList::Welcomelist, true, false) => tr!("Welcomelist Domain"),
+            (SenderList::Blocklist, false, false) => tr!("Blocklist Address"),
+            (SenderList::Blocklist, true, false) => tr!("Blocklist Domain"),
+            (SenderList::Welcomelist, false, true) => tr!("Remove Address from Welcomelist"),
+            (SenderList::Welcomelist, true, true) => tr!("Remove Domain from Welcomelist"),
+            (SenderList::Blocklist, false, true) => tr!("Remove Address from Blocklist"),
+            (SenderList::Blocklist, true, true) => tr!("Remove Domain from Blocklist"),
+        };
+        let icon_class = match (listed, list) {
+            (true, _) => "fa fa-minus-circle",
+            (false, SenderList::Welcomelist) => "fa fa-check",
+            (false, SenderList::Blocklist) => "fa fa-ban",
+        };
+
+        Button::new(label)
+            .icon_class(icon_class)
+            .class("pwt-button-text")
+            // wait for the current state before offering to change it
+            .disabled(!matches!(self.status, Some(Ok(_))))
+            .on_activate(ctx.link().callback(move |_| {
+                if listed {
+                    Msg::Remove(list, entry.clone())
+                } else {
+                    Msg::Add(list, entry.clone())
+                }
+            }))
+    }
+}
+
+impl Component for PmgSenderSheet {
+    type Message = Msg;
+    type Properties = SenderSheet;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        Self::load(ctx);
+        let (loaded, handle) = ctx
+            .link()
+            .context::<LoadedMails>(ctx.link().callback(Msg::LoadedChanged))
+            .unzip();
+        let mut me = Self {
+            status: None,
+            loaded: None,
+            _loaded_handle: handle,
+            _mails_observer: None,
+        };
+        if let Some(loaded) = loaded {
+            me.observe(ctx, loaded);
+        }
+        me
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::LoadResult(result) => self.status = Some(result),
+            Msg::LoadedChanged(loaded) => self.observe(ctx, loaded),
+            Msg::MailsChanged => {}
+            Msg::Add(list, entry) => {
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = add_to_sender_list(list, &entry).await;
+                    let message = tr!("Added {0} to {1}", entry, list.label());
+                    link.send_message(Msg::ActionResult(result, message));
+                });
+                return false;
+            }
+            Msg::Remove(list, entry) => {
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = remove_from_sender_list(list, &entry).await;
+                    let message = tr!("Removed {0} from {1}", entry, list.label());
+                    link.send_message(Msg::ActionResult(result, message));
+                });
+                return false;
+            }
+            Msg::ActionResult(result, message) => {
+                let message = match result {
+                    Ok(()) => message,
+                    Err(err) => err.to_string(),
+                };
+                announce(&message);
+                ctx.link().show_snackbar(SnackBar::new().message(message));
+                Self::load(ctx);
+                return false;
+            }
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let props = ctx.props();
+        let address = sender_address(&props.from);
+        let domain = sender_domain(&address).to_string();
+
+        let counts = self.loaded.as_ref().map(|loaded| {
+            let mails = loaded.0.read();
+            let count = |filter: &str| {
+                mails
+                    .iter()
+                    .filter(|mail| sender_matches(filter, &mail.from))
+                    .count()
+            };
+            (count(&address), count(&format!("@{domain}")))
+        });
+
+        let mut content = Column::new()
+            .padding(2)
+            .gap(2)
+            .class(JustifyContent::Stretch)
+            .with_child(
+                Column::new()
+                    .with_child(
+                        Container::new()
+                            .class("pwt-font-title-medium pwt-text-truncate")
+                            .with_child(&address),
+                    )
+                    .with_child(
+                        Container::new()
+                            .class("pwt-font-label-medium")
+                            .class(Opacity::Half)
+                            .with_child(tr!("Domain: {0}", domain)),
+                    ),
+            )
+            .with_child(self.status_view(&address, &domain));
+
+        if let Some((from_address, from_domain)) = counts {
+            content.add_child(
+                Container::new()
+                    .class("pwt-font-body-medium")
+                    .with_child(tr!(
+                        "{0} quarantined mails from this address, {1} from this domain in the shown range",
+                        from_address,
+                        from_domain
+                    )),
+            );
+        }
+
+        let mut actions = Column::new().class(JustifyContent::Stretch);
+        for list in [SenderList::Welcomelist, SenderList::Blocklist] {
+            actions.add_child(self.list_button(ctx, list, address.clone(), false));
+            actions.add_child(self.list_button(ctx, list, domain_pattern(&address), true));
+        }
+        content.add_child(actions);
+
+        let on_close = props.on_close.clone();
+        SideDialog::new()
+            .location(SideDialogLocation::Bottom)
+            .on_close(move |_| {
+                if let Some(on_close) = &on_close {
+                    on_close.emit(());
+                }
+            })
+            .with_child(content)
+            .into()
+    }
+}
+
+impl From<SenderSheet> for VNode {
+    fn from(val: SenderSheet) -> Self {
+        let comp = VComp::new::<PmgSenderSheet>(Rc::new(val), None);
+        VNode::from(comp)
+    }
+}
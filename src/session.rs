@@ -0,0 +1,387 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::time::Duration;
+
+use anyhow::{format_err, Error};
+use gloo_utils::window;
+use js_sys::Date;
+use proxmox_login::Authentication;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Headers, RequestInit, Response};
+use yew::platform::time::sleep;
+
+use pwt::prelude::*;
+
+use proxmox_yew_comp::{http_clear_auth, http_set_auth, stop_ticket_refresh_loop};
+
+use crate::settings::{load_item, remove_item, store_item};
+
+// User who logged out of an HttpOnly session, whose page the server may
+// still render with the session if it failed to clear the cookie.
+const LOGGED_OUT_KEY: &str = "pmg-quarantine-logged-out";
+
+/// Session the app runs with.
+#[derive(Clone)]
+pub(crate) enum LoginSession {
+    /// Ticket known to the app, from a login or a cookie it can read.
+    Ticket(Authentication),
+    /// Session of an HttpOnly cookie, which the browser sends along but the
+    /// app cannot read. The server renders the page with its user and CSRF
+    /// token, how long it stays valid is unknown.
+    HttpOnly { userid: String, csrf_token: String },
+}
+
+impl LoginSession {
+    pub fn userid(&self) -> &str {
+        match self {
+            Self::Ticket(info) => &info.userid,
+            Self::HttpOnly { userid, .. } => userid,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Session {
+    userid: Option<String>,
+    // end of the validity of a quarantine ticket
+    expires: Option<i64>,
+    // CSRF token of an HttpOnly session, sent with changing requests
+    csrf_token: Option<String>,
+    on_expired: Option<Callback<()>>,
+    // ends an HttpOnly session, which the auth observer knows nothing of
+    on_logout: Option<Callback<()>>,
+    // counts sessions, so timers of earlier ones do nothing
+    generation: usize,
+}
+
+thread_local! {
+    static SESSION: RefCell<Session> = RefCell::new(Session::default());
+}
+
+fn now() -> i64 {
+    (Date::now() / 1000.0) as i64
+}
+
+/// End of the validity of a quarantine ticket, from the creation time it
+/// carries, e.g. `PMGQUAR:user@example.com@quarantine:65F1A2B3::signature`.
+///
+/// Tickets from the links of spam reports cannot be renewed, so the session
+/// ends with them. The server accepts them for `lifetime` seconds.
+fn quarantine_ticket_expiry(ticket: &str, lifetime: i64) -> Option<i64> {
+    let rest = ticket.strip_prefix("PMGQUAR:")?;
+    let created = rest.split(':').nth(1)?;
+    let created = i64::from_str_radix(created, 16).ok()?;
+    Some(created + lifetime)
+}
+
+/// Sets up the client for `login` and watches the session, `on_expired` is
+/// called once the server rejects it or a quarantine ticket runs out. Without
+/// the `quarantine_lifetime` from the server, the end of a quarantine ticket
+/// is only noticed once the server rejects it, just like the end of an
+/// HttpOnly session. `on_logout` is called by [`log_out`] for HttpOnly
+/// sessions, ticket sessions end via the auth observer.
+pub(crate) fn start_session(
+    login: &LoginSession,
+    quarantine_lifetime: Option<i64>,
+    on_expired: Callback<()>,
+    on_logout: Callback<()>,
+) {
+    remove_item(LOGGED_OUT_KEY);
+    let (expires, csrf_token) = match login {
+        LoginSession::Ticket(info) => {
+            http_set_auth(info.clone());
+            let ticket = info.ticket.to_string();
+            // the tickets of spam report links cannot be renewed
+            if ticket.starts_with("PMGQUAR:") {
+                stop_ticket_refresh_loop();
+            }
+            let expires = quarantine_lifetime
+                .and_then(|lifetime| quarantine_ticket_expiry(&ticket, lifetime));
+            (expires, None)
+        }
+        LoginSession::HttpOnly { csrf_token, .. } => {
+            // without a ticket there is nothing the loop could renew
+            stop_ticket_refresh_loop();
+            (None, Some(csrf_token.clone()))
+        }
+    };
+    let generation = SESSION.with(|session| {
+        let mut session = session.borrow_mut();
+        session.generation += 1;
+        session.userid = Some(login.userid().to_string());
+        session.expires = expires;
+        session.csrf_token = csrf_token;
+        session.on_expired = Some(on_expired);
+        session.on_logout = Some(on_logout);
+        session.generation
+    });
+
+    if let Some(expires) = expires {
+        wasm_bindgen_futures::spawn_local(async move {
+            let remaining = (expires - now()).max(0) as u64;
+            sleep(Duration::from_secs(remaining)).await;
+            let current = SESSION.with(|session| session.borrow().generation == generation);
+            if current {
+                report_expired();
+            }
+        });
+    }
+}
+
+/// Ends the session, the app learns of it by the auth observer for ticket
+/// sessions and by the `on_logout` of [`start_session`] otherwise.
+pub(crate) fn log_out() {
+    let on_logout = SESSION.with(|session| {
+        let session = session.borrow();
+        session.csrf_token.as_ref().and(session.on_logout.clone())
+    });
+    match on_logout {
+        Some(on_logout) => on_logout.emit(()),
+        None => http_clear_auth(),
+    }
+}
+
+/// Forgets the session. An HttpOnly session also ends on the server, so the
+/// cookie is gone and the next page the server renders is not logged in.
+pub(crate) fn end_session(userid: &str) {
+    let csrf_token = SESSION.with(|session| {
+        let mut session = session.borrow_mut();
+        session.generation += 1;
+        session.userid = None;
+        session.expires = None;
+        session.on_expired = None;
+        session.on_logout = None;
+        session.csrf_token.take()
+    });
+    if let Some(csrf_token) = csrf_token {
+        // in case the server fails to clear the cookie
+        store_item(LOGGED_OUT_KEY, userid);
+        wasm_bindgen_futures::spawn_local(async move {
+            let result: Result<Value, Error> =
+                http_only_request("DELETE", "/access/ticket", None, &csrf_token).await;
+            if let Err(err) = result {
+                log::error!("could not log out on the server: {err}");
+            }
+        });
+    }
+}
+
+/// Whether `userid` logged out of an HttpOnly session, which then must not
+/// be resumed from the page.
+pub(crate) fn logged_out(userid: &str) -> bool {
+    load_item(LOGGED_OUT_KEY).is_some_and(|user| user == userid)
+}
+
+/// User of the running session.
+pub(crate) fn session_userid() -> Option<String> {
+    SESSION.with(|session| session.borrow().userid.clone())
+}
+
+/// Seconds until a quarantine ticket runs out, `None` for other sessions and
+/// if the server did not tell the lifetime of such tickets.
+pub(crate) fn session_remaining() -> Option<i64> {
+    let expires = SESSION.with(|session| session.borrow().expires)?;
+    Some((expires - now()).max(0))
+}
+
+/// Remaining validity in the largest sensible unit, e.g. "3 days".
+pub(crate) fn format_remaining(seconds: i64) -> String {
+    match seconds {
+        s if s >= 2 * 86400 => tr!("{0} days", s / 86400),
+        s if s >= 2 * 3600 => tr!("{0} hours", s / 3600),
+        s => tr!("{0} minutes", (s / 60).max(1)),
+    }
+}
+
+fn report_expired() {
+    let on_expired = SESSION.with(|session| session.borrow().on_expired.clone());
+    if let Some(on_expired) = on_expired {
+        on_expired.emit(());
+    }
+}
+
+/// Failed request of an HttpOnly session.
+#[derive(Debug)]
+struct StatusError {
+    status: u16,
+    message: String,
+}
+
+impl fmt::Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.status)
+    }
+}
+
+impl std::error::Error for StatusError {}
+
+// The server answers with 401 for missing, expired or otherwise invalid
+// tickets, the client without sending a request if it has no session.
+fn is_auth_error(err: &Error) -> bool {
+    if let Some(err) = err.downcast_ref::<StatusError>() {
+        return err.status == 401;
+    }
+    match err.downcast_ref::<proxmox_client::Error>() {
+        Some(proxmox_client::Error::Api(status, _)) => status.as_u16() == 401,
+        Some(proxmox_client::Error::Unauthorized) => true,
+        _ => false,
+    }
+}
+
+/// Whether the server answered that what `err` asked for does not exist,
+/// e.g. a mail that is no longer in the quarantine.
+pub(crate) fn is_not_found(err: &Error) -> bool {
+    if let Some(err) = err.downcast_ref::<StatusError>() {
+        return err.status == 404;
+    }
+    matches!(
+        err.downcast_ref::<proxmox_client::Error>(),
+        Some(proxmox_client::Error::Api(status, _)) if status.as_u16() == 404
+    )
+}
+
+fn form_encode(data: &Value) -> String {
+    let mut form = url::form_urlencoded::Serializer::new(String::new());
+    for (name, value) in data.as_object().into_iter().flatten() {
+        match value {
+            Value::Null => continue,
+            Value::String(text) => form.append_pair(name, text),
+            Value::Bool(flag) => form.append_pair(name, if *flag { "1" } else { "0" }),
+            other => form.append_pair(name, &other.to_string()),
+        };
+    }
+    form.finish()
+}
+
+// The client of proxmox-yew-comp sends no request without a ticket, so those
+// of HttpOnly sessions are sent here, with the CSRF token. The browser adds
+// the cookie.
+async fn http_only_request<T: DeserializeOwned>(
+    method: &str,
+    path: &str,
+    data: Option<Value>,
+    csrf_token: &str,
+) -> Result<T, Error> {
+    let js_err = |err: JsValue| format_err!("{err:?}");
+    let mut url = format!("/api2/json{path}");
+    let headers = Headers::new().map_err(js_err)?;
+    headers
+        .set("CSRFPreventionToken", csrf_token)
+        .map_err(js_err)?;
+    let init = RequestInit::new();
+    init.set_method(method);
+    if let Some(data) = &data {
+        let form = form_encode(data);
+        if method == "POST" {
+            headers
+                .set("Content-Type", "application/x-www-form-urlencoded")
+                .map_err(js_err)?;
+            init.set_body(&JsValue::from_str(&form));
+        } else if !form.is_empty() {
+            url = format!("{url}?{form}");
+        }
+    }
+    init.set_headers(&headers);
+
+    let response: Response = JsFuture::from(window().fetch_with_str_and_init(&url, &init))
+        .await
+        .map_err(js_err)?
+        .dyn_into()
+        .map_err(js_err)?;
+    if !response.ok() {
+        return Err(StatusError {
+            status: response.status(),
+            message: response.status_text(),
+        }
+        .into());
+    }
+    let text = JsFuture::from(response.text().map_err(js_err)?)
+        .await
+        .map_err(js_err)?
+        .as_string()
+        .unwrap_or_default();
+    let mut body: Value = serde_json::from_str(&text)?;
+    Ok(serde_json::from_value(body["data"].take())?)
+}
+
+fn http_only_csrf_token() -> Option<String> {
+    SESSION.with(|session| session.borrow().csrf_token.clone())
+}
+
+fn check<T>(result: Result<T, Error>) -> Result<T, Error> {
+    if let Err(err) = &result {
+        if is_auth_error(err) {
+            report_expired();
+        }
+    }
+    result
+}
+
+/// Like [`proxmox_yew_comp::http_get`], reporting a rejected session.
+pub(crate) async fn http_get<T: 'static + DeserializeOwned>(
+    path: impl Into<String>,
+    data: Option<Value>,
+) -> Result<T, Error> {
+    let path = path.into();
+    let result = match http_only_csrf_token() {
+        Some(token) => http_only_request("GET", &path, data, &token).await,
+        None => proxmox_yew_comp::http_get(path, data).await,
+    };
+    check(result)
+}
+
+/// Like [`proxmox_yew_comp::http_post`], reporting a rejected session.
+pub(crate) async fn http_post<T: 'static + DeserializeOwned>(
+    path: impl Into<String>,
+    data: Option<Value>,
+) -> Result<T, Error> {
+    let path = path.into();
+    let result = match http_only_csrf_token() {
+        Some(token) => http_only_request("POST", &path, data, &token).await,
+        None => proxmox_yew_comp::http_post(path, data).await,
+    };
+    check(result)
+}
+
+/// Like [`proxmox_yew_comp::http_delete`], reporting a rejected session.
+pub(crate) async fn http_delete(path: impl Into<String>, data: Option<Value>) -> Result<(), Error> {
+    let path = path.into();
+    let result = match http_only_csrf_token() {
+        Some(token) => http_only_request("DELETE", &path, data, &token).await,
+        None => proxmox_yew_comp::http_delete(path, data).await,
+    };
+    check(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quarantine_ticket_expiry_from_creation_time() {
+        let ticket = "PMGQUAR:user@example.com@quarantine:65F1A2B3::c2lnbmF0dXJl";
+        assert_eq!(
+            quarantine_ticket_expiry(ticket, 3600),
+            Some(0x65F1A2B3 + 3600)
+        );
+    }
+
+    #[test]
+    fn quarantine_ticket_expiry_of_other_tickets() {
+        // tickets of a login are renewed, their end is not of interest
+        let ticket = "PMG:user@pmg:65F1A2B3::c2lnbmF0dXJl";
+        assert_eq!(quarantine_ticket_expiry(ticket, 3600), None);
+        assert_eq!(
+            quarantine_ticket_expiry("PMGQUAR:user@example.com", 3600),
+            None
+        );
+        assert_eq!(
+            quarantine_ticket_expiry("PMGQUAR:user@example.com:nothex::sig", 3600),
+            None
+        );
+        assert_eq!(quarantine_ticket_expiry("", 3600), None);
+    }
+}
@@ -0,0 +1,64 @@
+use gloo_utils::window;
+use serde::{Deserialize, Serialize};
+
+use crate::date_format::clear_format_cache;
+use crate::tab_sync::{broadcast, TabEvent};
+
+const SETTINGS_KEY: &str = "pmg-quarantine-settings";
+
+/// Per-device user preferences, kept in the browser's local storage.
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Settings {
+    /// Raise browser notifications and set the app badge for new quarantined mail.
+    pub notifications: bool,
+    /// Avoid animations, e.g. show list rows without swipe actions.
+    pub reduced_motion: bool,
+    /// Cluster similar mails of a spam campaign in the list.
+    pub grouped: bool,
+    /// Show dates and times in this IANA time zone instead of the device's.
+    pub time_zone: Option<String>,
+    /// Log out after this many minutes without input, 0 for never. Only
+    /// applies if the server sets no timeout.
+    pub idle_timeout: Option<u32>,
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        load_item(SETTINGS_KEY)
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn store(&self) {
+        match serde_json::to_string(self) {
+            Ok(data) => {
+                store_item(SETTINGS_KEY, &data);
+                clear_format_cache();
+                broadcast(TabEvent::Settings);
+            }
+            Err(err) => log::error!("could not serialize settings: {err}"),
+        }
+    }
+}
+
+pub(crate) fn load_item(key: &str) -> Option<String> {
+    let storage = window().local_storage().ok().flatten()?;
+    storage.get_item(key).ok().flatten()
+}
+
+pub(crate) fn store_item(key: &str, value: &str) {
+    let storage = match window().local_storage() {
+        Ok(Some(storage)) => storage,
+        _ => return,
+    };
+    if let Err(err) = storage.set_item(key, value) {
+        log::error!("could not store '{key}': {err:?}");
+    }
+}
+
+pub(crate) fn remove_item(key: &str) {
+    if let Ok(Some(storage)) = window().local_storage() {
+        let _ = storage.remove_item(key);
+    }
+}
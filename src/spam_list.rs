@@ -1,34 +1,59 @@
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
 use std::{rc::Rc, str::FromStr};
 
 use anyhow::{format_err, Error};
-use gloo_utils::window;
-use js_sys::Date;
+use gloo_utils::{document, window};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use url::Url;
 use wasm_bindgen::JsValue;
+use web_sys::{KeyboardEvent, PointerEvent, ScrollIntoViewOptions, ScrollLogicalPosition};
 use yew::html::{IntoEventCallback, IntoPropValue};
+use yew::platform::time::sleep;
 use yew::virtual_dom::{VComp, VNode};
+use yew_router::scope_ext::RouterScopeExt;
 
 use pwt::{
     css::{AlignItems, ColorScheme, FlexFit, Opacity, Overflow},
     prelude::*,
-    state::SharedStateObserver,
+    state::{LanguageObserver, SharedStateObserver},
     touch::{Slidable, SlidableAction, SnackBar, SnackBarContextExt},
-    widget::{error_message, Container, Fa, List, ListTile, Progress, Row},
+    widget::menu::{Menu, MenuButton, MenuItem},
+    widget::{error_message, Button, Container, Fa, List, ListTile, Progress, Row},
 };
 
-use proxmox_yew_comp::http_get;
 use pwt::widget::Column;
 
-use crate::{mail_action, MailAction, QuarantineReload};
+use crate::a11y::announce;
+use crate::action_sheet::{action_sheet, batch_confirm_dialog, group_action_sheet};
+use crate::date_format::{clear_format_cache, day_key, day_label, format_time};
+use crate::history::{sync_history_guard, HistoryGuard};
+use crate::keyboard::{shortcut_help, KeyListener, Shortcut};
+use crate::list_dialog::{retroactive_snackbar, AddedEntry, ListDialog};
+use crate::sender_lists::{matching_mails, SenderList};
+use crate::sender_sheet::SenderSheet;
+use crate::session::http_get;
+use crate::{mail_action, mail_action_batch, LoadedMails, MailAction, QuarantineReload, Route};
 
-#[derive(Copy, Clone, Serialize, Default, PartialEq)]
+// DOM id of the row with keyboard focus, used to scroll it into view
+const FOCUSED_ROW_ID: &str = "pmg-spam-list-focused";
+
+// how long a touch has to rest on a row to open its actions
+const LONG_PRESS_DELAY: Duration = Duration::from_millis(500);
+
+// how far in pixels a touch may move before it counts as scrolling or swiping
+const LONG_PRESS_SLOP: i32 = 10;
+
+#[derive(Clone, Serialize, Default, PartialEq)]
 pub struct SpamListParam {
     #[serde(skip_serializing_if = "Option::is_none")]
     starttime: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     endtime: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pmail: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Clone, PartialEq)]
@@ -53,6 +78,21 @@ pub struct MailInfo {
     pub time: i64,
 }
 
+/// Loads the quarantined mails received in the given range, for the address
+/// `pmail` or the user's own.
+pub(crate) async fn load_mails(
+    starttime: Option<u64>,
+    endtime: Option<u64>,
+    pmail: Option<String>,
+) -> Result<Vec<MailInfo>, Error> {
+    let param = serde_json::to_value(SpamListParam {
+        starttime,
+        endtime,
+        pmail,
+    })?;
+    http_get("/quarantine/spam", Some(param)).await
+}
+
 fn deserialize_flexible_bool<'de, D>(deserializer: D) -> Result<bool, D::Error>
 where
     D: serde::Deserializer<'de>,
@@ -67,16 +107,204 @@ where
 
 #[derive(Clone)]
 pub enum ListEntry {
-    Date(String),
+    Date(DaySummary),
     Mail(MailInfo),
+    Group(MailGroup),
+}
+
+/// Order of the mails in the list.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListSort {
+    #[default]
+    Newest,
+    Oldest,
+}
+
+fn sort_mails(mails: &mut [MailInfo], sort: ListSort) {
+    mails.sort_by(|a, b| {
+        let order = match sort {
+            ListSort::Newest => b.time.cmp(&a.time),
+            ListSort::Oldest => a.time.cmp(&b.time),
+        };
+        order.then_with(|| a.id.cmp(&b.id))
+    });
+}
+
+/// Date header with the mails received on that day.
+#[derive(Clone, PartialEq)]
+pub struct DaySummary {
+    label: String,
+    ids: Vec<String>,
+    unseen_ids: Vec<String>,
+}
+
+// `mails` come with the time of the day they are listed under, which differs
+// from their own for the later mails of a group
+fn day_summaries<'a>(
+    mails: impl IntoIterator<Item = (i64, &'a MailInfo)>,
+) -> HashMap<String, DaySummary> {
+    let mut days: HashMap<String, DaySummary> = HashMap::new();
+    for (listed, mail) in mails {
+        let key = day_key(listed);
+        let day = days.entry(key).or_insert_with(|| DaySummary {
+            label: day_label(listed),
+            ids: Vec::new(),
+            unseen_ids: Vec::new(),
+        });
+        day.ids.push(mail.id.clone());
+        if !mail.seen {
+            day.unseen_ids.push(mail.id.clone());
+        }
+    }
+    days
+}
+
+/// Similar mails of a spam campaign, see [`campaign_key`].
+#[derive(Clone, PartialEq)]
+pub struct MailGroup {
+    key: String,
+    domain: String,
+    // in list order
+    mails: Vec<MailInfo>,
+}
+
+impl MailGroup {
+    fn ids(&self) -> Vec<String> {
+        self.mails.iter().map(|mail| mail.id.clone()).collect()
+    }
+
+    fn score_range(&self) -> (i64, i64) {
+        let scores = self.mails.iter().map(|mail| mail.spamlevel);
+        (scores.clone().min().unwrap_or(0), scores.max().unwrap_or(0))
+    }
+}
+
+// Spam waves vary numbers (order or tracking ids) and punctuation per mail and
+// prefix some with reply markers, so only the remaining words are compared.
+fn normalize_subject(subject: &str) -> String {
+    let mut subject = subject.trim().to_lowercase();
+    while let Some(rest) = ["re:", "fw:", "fwd:", "aw:", "wg:"]
+        .iter()
+        .find_map(|prefix| subject.strip_prefix(prefix))
+    {
+        subject = rest.trim_start().to_string();
+    }
+    subject
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Mails with the same normalized subject from the same sender domain are
+/// considered part of the same campaign.
+fn campaign_key(mail: &MailInfo) -> String {
+    let address = sender_address(&mail.from);
+    format!(
+        "{}\n{}",
+        sender_domain(&address),
+        normalize_subject(&mail.subject)
+    )
+}
+
+// `mails` need to be sorted by time, see [`sort_mails`]
+fn build_entries(mails: &[MailInfo], grouped: bool, expanded: &HashSet<String>) -> Vec<ListEntry> {
+    let mut res = Vec::new();
+    let mut last_date = String::new();
+    let mut push_date =
+        |res: &mut Vec<ListEntry>, days: &mut HashMap<String, DaySummary>, time: i64| {
+            let date = day_key(time);
+            if date != last_date {
+                if let Some(day) = days.remove(&date) {
+                    res.push(ListEntry::Date(day));
+                }
+                last_date = date;
+            }
+        };
+
+    if !grouped {
+        let mut days = day_summaries(mails.iter().map(|mail| (mail.time, mail)));
+        for mail in mails {
+            push_date(&mut res, &mut days, mail.time);
+            res.push(ListEntry::Mail(mail.clone()));
+        }
+        return res;
+    }
+
+    // groups are listed under the date of their first mail in list order
+    let mut groups: Vec<MailGroup> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for mail in mails {
+        let key = campaign_key(mail);
+        match positions.get(&key) {
+            Some(pos) => groups[*pos].mails.push(mail.clone()),
+            None => {
+                positions.insert(key.clone(), groups.len());
+                let address = sender_address(&mail.from);
+                groups.push(MailGroup {
+                    key,
+                    domain: sender_domain(&address).to_string(),
+                    mails: vec![mail.clone()],
+                });
+            }
+        }
+    }
+
+    let mut days = day_summaries(groups.iter().flat_map(|group| {
+        let listed = group.mails[0].time;
+        group.mails.iter().map(move |mail| (listed, mail))
+    }));
+    for group in groups {
+        push_date(&mut res, &mut days, group.mails[0].time);
+        if group.mails.len() == 1 {
+            res.extend(group.mails.into_iter().map(ListEntry::Mail));
+            continue;
+        }
+        let members = if expanded.contains(&group.key) {
+            group.mails.clone()
+        } else {
+            Vec::new()
+        };
+        res.push(ListEntry::Group(group));
+        res.extend(members.into_iter().map(ListEntry::Mail));
+    }
+    res
 }
 
 #[derive(Clone, PartialEq, Properties)]
 pub struct SpamList {
     #[prop_or_default]
     on_preview: Option<Callback<String>>,
+    /// Called with `true` once a dialog or sheet opens on top of the list,
+    /// and with `false` once all are closed again.
+    #[prop_or_default]
+    on_overlay: Option<Callback<bool>>,
     #[prop_or_default]
     param: SpamListParam,
+    /// Id of the mail currently shown in the preview, highlighted in the list.
+    #[prop_or_default]
+    selected: Option<String>,
+    /// Handle keyboard shortcuts. Mail actions and the help overlay are left
+    /// to the preview while a mail is selected.
+    #[prop_or_default]
+    keyboard: bool,
+    /// Render rows without swipe actions and their slide animations.
+    #[prop_or_default]
+    reduced_motion: bool,
+    /// Only show mails from this sender, see [`sender_matches`].
+    #[prop_or_default]
+    sender: Option<String>,
+    /// Cluster similar mails into expandable group rows.
+    #[prop_or_default]
+    grouped: bool,
+    /// Order of the mails.
+    #[prop_or_default]
+    sort: ListSort,
+    /// Time zone for dates and times, only used to notice changes of the
+    /// setting, see [`crate::date_format`].
+    #[prop_or_default]
+    time_zone: Option<String>,
 }
 
 impl SpamList {
@@ -94,36 +322,289 @@ impl SpamList {
         self
     }
 
+    /// Lists the quarantine of this address instead of the user's own.
+    pub fn pmail(mut self, address: impl IntoPropValue<Option<String>>) -> Self {
+        self.param.pmail = address.into_prop_value();
+        self
+    }
+
+    pub fn sender(mut self, sender: impl IntoPropValue<Option<String>>) -> Self {
+        self.sender = sender.into_prop_value();
+        self
+    }
+
+    pub fn selected(mut self, id: impl IntoPropValue<Option<String>>) -> Self {
+        self.selected = id.into_prop_value();
+        self
+    }
+
+    pub fn keyboard(mut self, keyboard: bool) -> Self {
+        self.keyboard = keyboard;
+        self
+    }
+
+    pub fn grouped(mut self, grouped: bool) -> Self {
+        self.grouped = grouped;
+        self
+    }
+
+    pub fn sort(mut self, sort: ListSort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    pub fn time_zone(mut self, time_zone: impl IntoPropValue<Option<String>>) -> Self {
+        self.time_zone = time_zone.into_prop_value();
+        self
+    }
+
+    pub fn reduced_motion(mut self, reduced_motion: bool) -> Self {
+        self.reduced_motion = reduced_motion;
+        self
+    }
+
     pub fn on_preview(mut self, cb: impl IntoEventCallback<String>) -> Self {
         self.on_preview = cb.into_event_callback();
         self
     }
+
+    pub fn on_overlay(mut self, cb: impl IntoEventCallback<bool>) -> Self {
+        self.on_overlay = cb.into_event_callback();
+        self
+    }
 }
 
 pub enum Msg {
     Reload,
-    Action(String, MailAction), // id
+    Action(String, MailAction),           // id
+    BatchAction(Vec<String>, MailAction), // ids
     LoadResult(Result<Vec<MailInfo>, Error>),
+    Key(KeyboardEvent),
+    CloseShortcuts,
+    ShowActions(MailInfo),
+    CloseActions,
+    ToggleGroup(String), // key
+    ShowGroupActions(MailGroup),
+    CloseGroupActions,
+    ConfirmBatch(Vec<String>, MailAction), // ids
+    CancelBatch,
+    Select(Vec<String>),  // ids
+    ToggleSelect(String), // id
+    ClearSelection,
+    ShowSender(String), // From header
+    CloseSender,
+    CloseListDialog,
+    EntryAdded(String, AddedEntry), // id
+    LanguageChanged,
+    Back,
 }
 
 pub struct PmgSpamList {
+    // the loaded mails, filtered and sorted as requested
+    mails: Vec<MailInfo>,
     data: Option<Result<Vec<ListEntry>, Error>>,
     // kept alive to keep the reload listener registered on the shared trigger
     _reload_observer: Option<SharedStateObserver<usize>>,
+    // position of the row with keyboard focus in `data`
+    focused: Option<usize>,
+    scroll_to_focused: bool,
+    // last state passed to `on_overlay`
+    overlay_reported: bool,
+    show_shortcuts: bool,
+    _key_listener: KeyListener,
+    action_sheet: Option<MailInfo>,
+    // keys of expanded groups
+    expanded: HashSet<String>,
+    group_sheet: Option<MailGroup>,
+    // batch action waiting for confirmation
+    pending_batch: Option<(Vec<String>, MailAction)>,
+    // ids of the mails selected for a batch action
+    selection: HashSet<String>,
+    // From header of the mail whose sender sheet is shown
+    sender_sheet: Option<String>,
+    // mail whose sender is about to be welcomelisted or blocklisted
+    list_dialog: Option<(MailInfo, SenderList)>,
+    loaded: Option<LoadedMails>,
+    // history entry while an overlay or the selection is open
+    history_guard: Option<HistoryGuard>,
+    long_press: LongPress,
+    // rebuilds the date headers, whose labels are made with the list
+    _language_observer: LanguageObserver,
 }
 
 impl PmgSpamList {
+    fn selection_bar(&self, ctx: &Context<Self>) -> Option<Html> {
+        if self.selection.is_empty() {
+            return None;
+        }
+        let ids: Vec<String> = self.selection.iter().cloned().collect();
+        let mut bar = Row::new()
+            .class(AlignItems::Center)
+            .class(ColorScheme::SecondaryContainer)
+            .gap(1)
+            .padding(1)
+            .with_child(
+                Button::new_icon("fa fa-times")
+                    .class("circle")
+                    .attribute("aria-label", tr!("Clear selection"))
+                    .on_activate(ctx.link().callback(|_| Msg::ClearSelection)),
+            )
+            .with_child(
+                Container::new()
+                    .class(FlexFit)
+                    .attribute("aria-live", "polite")
+                    .with_child(tr!("{0} selected", ids.len())),
+            );
+        for action in [
+            MailAction::Deliver,
+            MailAction::MarkSeen,
+            MailAction::Delete,
+        ] {
+            let ids = ids.clone();
+            bar.add_child(
+                Button::new_icon(action.icon_class())
+                    .class("circle")
+                    .attribute("aria-label", action.label())
+                    .attribute("title", action.label())
+                    .on_activate(
+                        ctx.link()
+                            .callback(move |_| Msg::ConfirmBatch(ids.clone(), action)),
+                    ),
+            );
+        }
+        Some(bar.into())
+    }
+
+    fn rebuild_entries(&mut self, ctx: &Context<Self>) {
+        if let Some(Ok(_)) = &self.data {
+            let entries = build_entries(&self.mails, ctx.props().grouped, &self.expanded);
+            self.data = Some(Ok(entries));
+            self.clamp_focus();
+        }
+    }
+
     fn load(&self, ctx: &Context<Self>) {
         let props = ctx.props();
         let link = ctx.link().clone();
-        let param: Value = serde_json::to_value(props.param).unwrap();
+        let SpamListParam {
+            starttime,
+            endtime,
+            pmail,
+        } = props.param.clone();
 
         wasm_bindgen_futures::spawn_local(async move {
-            let result = http_get::<Vec<MailInfo>>("/quarantine/spam", Some(param)).await;
+            let result = load_mails(starttime, endtime, pmail).await;
 
             link.send_message(Msg::LoadResult(result));
         })
     }
+
+    fn focused_entry(&self) -> Option<&ListEntry> {
+        match (&self.data, self.focused) {
+            (Some(Ok(data)), Some(pos)) => data.get(pos),
+            _ => None,
+        }
+    }
+
+    fn focused_mail(&self) -> Option<&MailInfo> {
+        match self.focused_entry() {
+            Some(ListEntry::Mail(mail)) => Some(mail),
+            _ => None,
+        }
+    }
+
+    // moves the keyboard focus to the next resp. previous mail or group,
+    // skipping date headers
+    fn move_focus(&mut self, forward: bool) {
+        let Some(Ok(data)) = &self.data else {
+            return;
+        };
+        let is_stop = |pos: &usize| !matches!(data[*pos], ListEntry::Date(_));
+        let next = match (self.focused, forward) {
+            (None, _) => (0..data.len()).find(is_stop),
+            (Some(pos), true) => (pos + 1..data.len()).find(is_stop),
+            (Some(pos), false) => (0..pos).rev().find(is_stop),
+        };
+        if next.is_some() {
+            self.focused = next;
+            self.scroll_to_focused = true;
+        }
+    }
+
+    // keeps the focus on the same position after a reload, so the next mail
+    // gets focused once the focused one was delivered or deleted
+    fn clamp_focus(&mut self) {
+        let (Some(Ok(data)), Some(pos)) = (&self.data, self.focused) else {
+            return;
+        };
+        let is_stop = |pos: &usize| !matches!(data[*pos], ListEntry::Date(_));
+        self.focused = (pos.min(data.len())..data.len())
+            .find(is_stop)
+            .or_else(|| (0..pos.min(data.len())).rev().find(is_stop));
+    }
+
+    // dialogs and sheets on top of the list, which take the keyboard
+    fn overlay_open(&self) -> bool {
+        self.list_dialog.is_some()
+            || self.pending_batch.is_some()
+            || self.sender_sheet.is_some()
+            || self.group_sheet.is_some()
+            || self.action_sheet.is_some()
+    }
+
+    // returns whether the shortcut was handled
+    fn handle_shortcut(&mut self, ctx: &Context<Self>, shortcut: Shortcut) -> bool {
+        let props = ctx.props();
+        if !props.keyboard || self.overlay_open() {
+            return false;
+        }
+        // the help only closes
+        if self.show_shortcuts && shortcut != Shortcut::Back {
+            return false;
+        }
+        let preview_open = props.selected.is_some();
+
+        match shortcut {
+            Shortcut::Next => self.move_focus(true),
+            Shortcut::Previous => self.move_focus(false),
+            Shortcut::Open => match (self.focused_entry(), &props.on_preview) {
+                (Some(ListEntry::Mail(mail)), Some(on_preview)) => on_preview.emit(mail.id.clone()),
+                (Some(ListEntry::Group(group)), _) => {
+                    ctx.link().send_message(Msg::ToggleGroup(group.key.clone()))
+                }
+                _ => return false,
+            },
+            Shortcut::Back if !preview_open => {
+                if self.show_shortcuts {
+                    self.show_shortcuts = false;
+                } else if self.focused.take().is_none() {
+                    return false;
+                }
+            }
+            Shortcut::Action(action) if !preview_open => {
+                let Some(mail) = self.focused_mail() else {
+                    return false;
+                };
+                ctx.link()
+                    .send_message(Msg::Action(mail.id.clone(), action));
+            }
+            Shortcut::ToggleSeen if !preview_open => {
+                let Some(mail) = self.focused_mail() else {
+                    return false;
+                };
+                let action = if mail.seen {
+                    MailAction::MarkUnseen
+                } else {
+                    MailAction::MarkSeen
+                };
+                ctx.link()
+                    .send_message(Msg::Action(mail.id.clone(), action));
+            }
+            Shortcut::Help if !preview_open => self.show_shortcuts = true,
+            _ => return false,
+        }
+        true
+    }
 }
 
 impl Component for PmgSpamList {
@@ -140,14 +621,44 @@ impl Component for PmgSpamList {
             .map(|(reload, _handle)| reload.0.add_listener(ctx.link().callback(|_| Msg::Reload)));
 
         let me = Self {
+            mails: Vec::new(),
             data: None,
             _reload_observer: reload_observer,
+            focused: None,
+            scroll_to_focused: false,
+            overlay_reported: false,
+            show_shortcuts: false,
+            _key_listener: KeyListener::new(ctx.link().callback(Msg::Key)),
+            action_sheet: None,
+            expanded: HashSet::new(),
+            group_sheet: None,
+            pending_batch: None,
+            selection: HashSet::new(),
+            sender_sheet: None,
+            list_dialog: None,
+            loaded: ctx
+                .link()
+                .context::<LoadedMails>(Callback::noop())
+                .map(|(loaded, _handle)| loaded),
+            history_guard: None,
+            long_press: LongPress::default(),
+            _language_observer: LanguageObserver::new(
+                ctx.link().callback(|_| Msg::LanguageChanged),
+            ),
         };
 
-        match extract_mail_action_from_query_params() {
+        match extract_report_link_from_query_params() {
             Ok(None) => {}
-            Ok(Some((id, action))) => {
-                ctx.link().send_message(Msg::Action(id, action));
+            Ok(Some(ReportLink::View(id))) => {
+                if let Some(on_preview) = &ctx.props().on_preview {
+                    on_preview.emit(id);
+                }
+            }
+            // the result page runs the action and reports back
+            Ok(Some(ReportLink::Action(id, action))) => {
+                if let Some(navigator) = ctx.link().navigator() {
+                    navigator.push(&Route::ActionResult { action, id });
+                }
             }
             Err(err) => {
                 ctx.link().show_snackbar(
@@ -167,31 +678,129 @@ impl Component for PmgSpamList {
             }
             Msg::LoadResult(result) => match result {
                 Ok(mut data) => {
-                    data.sort_by(|a, b| b.time.cmp(&a.time).then_with(|| a.id.cmp(&b.id)));
-
-                    let mut res = Vec::new();
-                    let mut last_date = String::new();
-
-                    for mail in data {
-                        let date = epoch_to_date(mail.time);
-                        if date != last_date {
-                            res.push(ListEntry::Date(date.clone()));
-                            last_date = date;
-                        }
-                        res.push(ListEntry::Mail(mail));
+                    // the views on top relate a mail to the whole range, not
+                    // only to the mails of the sender filter
+                    if let Some(loaded) = &self.loaded {
+                        **loaded.0.write() = data.clone();
+                    }
+                    if let Some(sender) = &ctx.props().sender {
+                        data.retain(|mail| sender_matches(sender, &mail.from));
                     }
+                    sort_mails(&mut data, ctx.props().sort);
 
-                    self.data = Some(Ok(res));
+                    // drop selected mails that are gone, e.g. handled in another view
+                    self.selection
+                        .retain(|id| data.iter().any(|mail| &mail.id == id));
+                    self.mails = data;
+                    self.data = Some(Ok(Vec::new()));
+                    self.rebuild_entries(ctx);
                 }
                 Err(err) => self.data = Some(Err(err)),
             },
+            Msg::Key(event) => {
+                let Some(shortcut) = Shortcut::from_event(&event) else {
+                    return false;
+                };
+                if !self.handle_shortcut(ctx, shortcut) {
+                    return false;
+                }
+                event.prevent_default();
+            }
+            Msg::CloseShortcuts => self.show_shortcuts = false,
+            Msg::ShowActions(mail) => self.action_sheet = Some(mail),
+            Msg::CloseActions => self.action_sheet = None,
+            Msg::ToggleGroup(key) => {
+                if !self.expanded.remove(&key) {
+                    self.expanded.insert(key);
+                }
+                self.rebuild_entries(ctx);
+            }
+            Msg::ShowGroupActions(group) => self.group_sheet = Some(group),
+            Msg::CloseGroupActions => self.group_sheet = None,
+            Msg::ConfirmBatch(ids, action) => self.pending_batch = Some((ids, action)),
+            Msg::CancelBatch => self.pending_batch = None,
+            Msg::Select(ids) => self.selection.extend(ids),
+            Msg::ToggleSelect(id) => {
+                if !self.selection.remove(&id) {
+                    self.selection.insert(id);
+                }
+            }
+            Msg::ClearSelection => self.selection.clear(),
+            Msg::ShowSender(from) => self.sender_sheet = Some(from),
+            Msg::CloseSender => self.sender_sheet = None,
+            Msg::BatchAction(ids, action) => {
+                self.pending_batch = None;
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let msg = match mail_action_batch(&ids, action).await {
+                        Ok(_) => {
+                            link.send_message(Msg::ClearSelection);
+                            tr!("Action '{0}' successful for {1} mails", action, ids.len())
+                        }
+                        Err(err) => err.to_string(),
+                    };
+                    announce(&msg);
+                    link.show_snackbar(SnackBar::new().message(msg));
+                    link.send_message(Msg::Reload);
+                });
+            }
+            Msg::CloseListDialog => self.list_dialog = None,
+            Msg::LanguageChanged => {
+                // the formatters of the old language are no longer needed
+                clear_format_cache();
+                self.rebuild_entries(ctx);
+            }
+            // close the topmost overlay, the selection counts as one
+            Msg::Back => {
+                if self.list_dialog.take().is_none()
+                    && self.pending_batch.take().is_none()
+                    && self.sender_sheet.take().is_none()
+                    && self.group_sheet.take().is_none()
+                    && self.action_sheet.take().is_none()
+                    && !std::mem::take(&mut self.show_shortcuts)
+                {
+                    self.selection.clear();
+                }
+            }
+            Msg::EntryAdded(id, added) => {
+                let link = ctx.link().clone();
+                let mut others = matching_mails(&self.mails, &added.entry, &id);
+                wasm_bindgen_futures::spawn_local(async move {
+                    let mut msg = tr!("Added {0} to {1}", added.entry, added.list.label());
+                    if let Some(action) = added.followup {
+                        if let Err(err) = mail_action(&id, action).await {
+                            msg = err.to_string();
+                            // nothing to build on
+                            others.clear();
+                        }
+                    }
+                    announce(&msg);
+                    link.show_snackbar(retroactive_snackbar(
+                        msg,
+                        added.list,
+                        others,
+                        link.callback(|(ids, action)| Msg::ConfirmBatch(ids, action)),
+                    ));
+                    link.send_message(Msg::Reload);
+                });
+                return false;
+            }
             Msg::Action(id, action) => {
+                // let the user choose what exactly to list; actions from mail
+                // links arrive before the list is loaded and run directly
+                if let Some(list) = SenderList::from_action(action) {
+                    if let Some(mail) = self.mails.iter().find(|mail| mail.id == id) {
+                        self.list_dialog = Some((mail.clone(), list));
+                        return true;
+                    }
+                }
                 let link = ctx.link().clone();
                 wasm_bindgen_futures::spawn_local(async move {
                     let msg = match mail_action(&id, action).await {
                         Ok(_) => tr!("Action '{0}' successful", action),
                         Err(err) => err.to_string(),
                     };
+                    announce(&msg);
                     link.show_snackbar(SnackBar::new().message(msg));
                     link.send_message(Msg::Reload);
                 });
@@ -204,27 +813,130 @@ impl Component for PmgSpamList {
     fn changed(&mut self, ctx: &Context<Self>, old_props: &Self::Properties) -> bool {
         let props = ctx.props();
 
-        if props.param != old_props.param {
+        if props.param != old_props.param || props.sender != old_props.sender {
             self.load(ctx);
+        } else if props.sort != old_props.sort {
+            sort_mails(&mut self.mails, props.sort);
+            self.rebuild_entries(ctx);
+        } else if props.grouped != old_props.grouped || props.time_zone != old_props.time_zone {
+            self.rebuild_entries(ctx);
         }
 
         true
     }
 
+    fn rendered(&mut self, ctx: &Context<Self>, _first_render: bool) {
+        let open = self.overlay_open() || self.show_shortcuts || !self.selection.is_empty();
+        sync_history_guard(&mut self.history_guard, open, || {
+            ctx.link().callback(|_| Msg::Back)
+        });
+
+        let overlay = self.overlay_open() || self.show_shortcuts;
+        if overlay != self.overlay_reported {
+            self.overlay_reported = overlay;
+            if let Some(on_overlay) = &ctx.props().on_overlay {
+                on_overlay.emit(overlay);
+            }
+        }
+
+        if std::mem::take(&mut self.scroll_to_focused) {
+            if let Some(element) = document().get_element_by_id(FOCUSED_ROW_ID) {
+                let options = ScrollIntoViewOptions::new();
+                options.set_block(ScrollLogicalPosition::Nearest);
+                element.scroll_into_view_with_scroll_into_view_options(&options);
+            }
+        }
+    }
+
     fn view(&self, ctx: &Context<Self>) -> Html {
-        match &self.data {
+        let help: Option<Html> = self
+            .show_shortcuts
+            .then(|| shortcut_help(ctx.link().callback(|_| Msg::CloseShortcuts)).into());
+
+        let action_sheet: Option<Html> = self.action_sheet.as_ref().map(|mail| {
+            let id = mail.id.clone();
+            let on_preview = ctx.props().on_preview.clone().map(|on_preview| {
+                let id = id.clone();
+                Callback::from(move |_| on_preview.emit(id.clone()))
+            });
+            let from = mail.from.clone();
+            action_sheet(
+                mail,
+                on_preview,
+                ctx.link().callback(move |_| Msg::ShowSender(from.clone())),
+                ctx.link()
+                    .callback(move |action| Msg::Action(id.clone(), action)),
+                ctx.link().callback(|_| Msg::CloseActions),
+            )
+            .into()
+        });
+
+        let group_sheet: Option<Html> = self.group_sheet.as_ref().map(|group| {
+            let ids = group.ids();
+            group_action_sheet(
+                &group.domain,
+                ids.len(),
+                ctx.link()
+                    .callback(move |action| Msg::ConfirmBatch(ids.clone(), action)),
+                ctx.link().callback(|_| Msg::CloseGroupActions),
+            )
+            .into()
+        });
+
+        let sender_sheet: Option<Html> = self.sender_sheet.as_ref().map(|from| {
+            SenderSheet::new(from.clone())
+                .on_close(ctx.link().callback(|_| Msg::CloseSender))
+                .into()
+        });
+
+        let list_dialog: Option<Html> = self.list_dialog.as_ref().map(|(mail, list)| {
+            let id = mail.id.clone();
+            ListDialog::new(*list, mail.from.clone())
+                .on_close(ctx.link().callback(|_| Msg::CloseListDialog))
+                .on_done(
+                    ctx.link()
+                        .callback(move |added| Msg::EntryAdded(id.clone(), added)),
+                )
+                .into()
+        });
+
+        let confirm: Option<Html> = self.pending_batch.as_ref().map(|(ids, action)| {
+            let ids = ids.clone();
+            let action = *action;
+            batch_confirm_dialog(
+                action,
+                ids.len(),
+                ctx.link()
+                    .callback(move |_| Msg::BatchAction(ids.clone(), action)),
+                ctx.link().callback(|_| Msg::CancelBatch),
+            )
+            .into()
+        });
+
+        let list: Html = match &self.data {
             Some(Ok(data)) if !data.is_empty() => {
-                let on_preview = ctx.props().on_preview.clone();
+                let props = ctx.props();
+                let options = RowOptions {
+                    on_preview: props.on_preview.clone(),
+                    selected: props.selected.clone(),
+                    reduced_motion: props.reduced_motion,
+                    expanded: self.expanded.clone(),
+                    selection: self.selection.clone(),
+                    long_press: self.long_press.clone(),
+                };
+                let focused = self.focused;
                 let data = data.clone();
                 let link = ctx.link().clone();
                 List::new(data.len() as u64, move |pos: u64| {
                     ListTile::new().padding(0).with_child(render_list_item(
                         &link,
-                        on_preview.clone(),
+                        &options,
+                        focused == Some(pos as usize),
                         &data[pos as usize],
                     ))
                 })
                 .class(FlexFit)
+                .attribute("aria-label", tr!("Quarantined mails"))
                 .into()
             }
             Some(Ok(_)) => Row::new()
@@ -233,7 +945,9 @@ impl Component for PmgSpamList {
                 .into(),
             Some(Err(err)) => error_message(&err.to_string()).into(),
             None => Progress::new().into(),
-        }
+        };
+
+        html! {<>{self.selection_bar(ctx)}{list}{help}{action_sheet}{group_sheet}{sender_sheet}{list_dialog}{confirm}</>}
     }
 }
 
@@ -244,29 +958,290 @@ impl From<SpamList> for VNode {
     }
 }
 
+// list wide state the rows depend on
+struct RowOptions {
+    on_preview: Option<Callback<String>>,
+    selected: Option<String>,
+    reduced_motion: bool,
+    expanded: HashSet<String>,
+    selection: HashSet<String>,
+    long_press: LongPress,
+}
+
+/// Detects long-presses by touch. Browsers open the context menu for them,
+/// except iOS Safari, which fires no `contextmenu` event at all.
+#[derive(Clone, Default)]
+struct LongPress {
+    // where the current touch started, cleared once it ends or moves away
+    start: Rc<Cell<Option<(i32, i32)>>>,
+    // tells the timer of the current touch from those of earlier ones
+    touch: Rc<Cell<usize>>,
+    // the current touch was a long-press, so lifting it is no tap
+    fired: Rc<Cell<bool>>,
+}
+
+impl LongPress {
+    // Calls `on_press` once a touch rested on `row` long enough. Where the
+    // browser fires `contextmenu` as well, both open the same sheet.
+    fn attach(&self, row: Row, on_press: Callback<()>) -> Row {
+        let end = {
+            let start = self.start.clone();
+            move |_: PointerEvent| start.set(None)
+        };
+        row.onpointerdown({
+            let this = self.clone();
+            move |event: PointerEvent| {
+                if event.pointer_type() != "touch" {
+                    return;
+                }
+                this.start.set(Some((event.client_x(), event.client_y())));
+                this.fired.set(false);
+                let touch = this.touch.get() + 1;
+                this.touch.set(touch);
+
+                let this = this.clone();
+                let on_press = on_press.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    sleep(LONG_PRESS_DELAY).await;
+                    if this.touch.get() == touch && this.start.take().is_some() {
+                        this.fired.set(true);
+                        on_press.emit(());
+                    }
+                });
+            }
+        })
+        .onpointermove({
+            let start = self.start.clone();
+            move |event: PointerEvent| {
+                if let Some((x, y)) = start.get() {
+                    if (event.client_x() - x).abs() > LONG_PRESS_SLOP
+                        || (event.client_y() - y).abs() > LONG_PRESS_SLOP
+                    {
+                        start.set(None);
+                    }
+                }
+            }
+        })
+        .onpointerup(end.clone())
+        .onpointercancel(end)
+    }
+
+    // Whether the tap that ends a touch is to be ignored, as the touch opened
+    // the sheet already.
+    fn take_fired(&self) -> bool {
+        self.fired.replace(false)
+    }
+}
+
+// Enter and Space activate rows with the role of a button, like they do
+// native buttons. The event stops here, so the list shortcuts do not see it.
+pub(crate) fn button_keydown(on_activate: Callback<()>) -> Callback<KeyboardEvent> {
+    Callback::from(move |event: KeyboardEvent| {
+        if matches!(event.key().as_str(), "Enter" | " ") {
+            event.prevent_default();
+            event.stop_propagation();
+            on_activate.emit(());
+        }
+    })
+}
+
+fn render_date(link: &yew::html::Scope<PmgSpamList>, day: &DaySummary) -> Html {
+    let mut menu = Menu::new();
+    if !day.unseen_ids.is_empty() {
+        let ids = day.unseen_ids.clone();
+        menu.add_item(
+            MenuItem::new(tr!("Mark all as seen"))
+                .icon_class(MailAction::MarkSeen.icon_class())
+                .on_select(
+                    link.callback(move |_| Msg::ConfirmBatch(ids.clone(), MailAction::MarkSeen)),
+                ),
+        );
+    }
+    menu.add_item(
+        MenuItem::new(tr!("Delete all from this day"))
+            .icon_class(MailAction::Delete.icon_class())
+            .on_select({
+                let ids = day.ids.clone();
+                link.callback(move |_| Msg::ConfirmBatch(ids.clone(), MailAction::Delete))
+            }),
+    );
+    menu.add_item(
+        MenuItem::new(tr!("Select all from this day"))
+            .icon_class("fa fa-check-square-o")
+            .on_select({
+                let ids = day.ids.clone();
+                link.callback(move |_| Msg::Select(ids.clone()))
+            }),
+    );
+
+    Row::new()
+        .padding_start(1)
+        .gap(1)
+        .class(AlignItems::Center)
+        .class(ColorScheme::Surface)
+        .class("pwt-default-colors")
+        .with_child(
+            Container::new()
+                .class(FlexFit)
+                .attribute("role", "heading")
+                .attribute("aria-level", "2")
+                .with_child(&day.label),
+        )
+        .with_child(
+            Container::new()
+                .class("pwt-font-label-small")
+                .class(Opacity::Half)
+                .with_child(tr!(
+                    "{0} mails, {1} unseen",
+                    day.ids.len(),
+                    day.unseen_ids.len()
+                )),
+        )
+        .with_child(
+            MenuButton::new("")
+                .class("circle")
+                .icon_class("fa fa-ellipsis-v")
+                .attribute("aria-label", tr!("Actions for {0}", day.label))
+                .menu(menu),
+        )
+        .into()
+}
+
+fn render_group(
+    link: &yew::html::Scope<PmgSpamList>,
+    options: &RowOptions,
+    focused: bool,
+    group: &MailGroup,
+) -> Html {
+    let expanded = options.expanded.contains(&group.key);
+    let count = group.mails.len();
+    let subject = &group.mails[0].subject;
+    let (min_score, max_score) = group.score_range();
+    let score = if min_score == max_score {
+        tr!("Score: {0}", min_score)
+    } else {
+        tr!("Score: {0} – {1}", min_score, max_score)
+    };
+
+    let mut main = Row::new()
+        .class(FlexFit)
+        .gap(1)
+        .padding_x(2)
+        .padding_y(1)
+        .class(AlignItems::Center)
+        .style("cursor", "pointer")
+        .attribute("role", "button")
+        .attribute("tabindex", "0")
+        .attribute("aria-expanded", if expanded { "true" } else { "false" })
+        .attribute(
+            "aria-label",
+            tr!(
+                "{0} similar mails from {1}, subject {2}",
+                count,
+                group.domain,
+                subject
+            ),
+        )
+        .onclick({
+            let key = group.key.clone();
+            link.callback(move |_| Msg::ToggleGroup(key.clone()))
+        })
+        .onkeydown(button_keydown({
+            let key = group.key.clone();
+            link.callback(move |()| Msg::ToggleGroup(key.clone()))
+        }));
+    if focused {
+        main = main
+            .attribute("id", FOCUSED_ROW_ID)
+            .style("outline", "2px solid var(--pwt-color-primary)")
+            .style("outline-offset", "-2px");
+    }
+    let main = main
+        .with_child(
+            Fa::new(if expanded {
+                "chevron-down"
+            } else {
+                "chevron-right"
+            })
+            .attribute("aria-hidden", "true"),
+        )
+        .with_child(
+            Column::new()
+                .class(FlexFit)
+                .with_child(html! {
+                    <div class="pwt-font-label-small pwt-text-truncate">{&group.domain}</div>
+                })
+                .with_child(html! {
+                    <div class="pwt-font-title-small pwt-text-truncate">{subject}</div>
+                }),
+        )
+        .with_child(
+            Column::new()
+                .class("pwt-white-space-nowrap")
+                .class(Opacity::Half)
+                .with_child(tr!("{0} mails", count))
+                .with_child(html! { <span class="pwt-font-label-small">{score}</span> }),
+        );
+
+    let more = Button::new_icon("fa fa-ellipsis-v")
+        .class("circle")
+        .attribute("aria-label", tr!("Actions for all mails of this group"))
+        .attribute("aria-haspopup", "menu")
+        .on_activate({
+            let group = group.clone();
+            link.callback(move |_| Msg::ShowGroupActions(group.clone()))
+        });
+
+    Row::new()
+        .class(AlignItems::Center)
+        .border_bottom(true)
+        .with_child(main)
+        .with_child(more)
+        .into()
+}
+
 fn render_list_item(
     link: &yew::html::Scope<PmgSpamList>,
-    on_preview: Option<Callback<String>>,
+    options: &RowOptions,
+    focused: bool,
     item: &ListEntry,
 ) -> Html {
     match item {
-        ListEntry::Date(date) => Container::new()
-            .padding(1)
-            .class(ColorScheme::Surface)
-            .class("pwt-default-colors")
-            .with_child(date)
-            .into(),
+        ListEntry::Date(day) => render_date(link, day),
+        ListEntry::Group(group) => render_group(link, options, focused, group),
         ListEntry::Mail(item) => {
             let make_cb = |action: MailAction| {
                 let id = item.id.clone();
                 link.callback(move |_| Msg::Action(id.clone(), action))
             };
+            // while selecting, tapping a row toggles its selection
+            let selecting = !options.selection.is_empty();
+            let open = if selecting {
+                let id = item.id.clone();
+                link.callback(move |()| Msg::ToggleSelect(id.clone()))
+            } else {
+                let id = item.id.clone();
+                let on_preview = options.on_preview.clone();
+                Callback::from(move |()| {
+                    if let Some(on_preview) = &on_preview {
+                        on_preview.emit(id.clone())
+                    }
+                })
+            };
 
             let content = Column::new()
                 .class(FlexFit)
-                .with_child(html! {
-                    <div class="pwt-font-label-small pwt-text-truncate">{&item.from}</div>
-                })
+                .with_child(
+                    Row::new()
+                        .gap(1)
+                        .class("pwt-font-label-small")
+                        .with_child(html! {
+                            <div class="pwt-text-truncate pwt-flex-fill">{&item.from}</div>
+                        })
+                        .with_child(html! {
+                            <div class="pwt-white-space-nowrap">{format_time(item.time)}</div>
+                        }),
+                )
                 .with_child(html! {
                     <div class="pwt-font-title-small pwt-text-truncate">{&item.subject}</div>
                 });
@@ -281,92 +1256,201 @@ fn render_list_item(
                         { format!("+{:.1} / {:.1}", item.score_positive, item.score_negative) }
                     </span>
                 });
+
+            let label = if item.seen {
+                tr!(
+                    "From {0}, subject {1}, score {2}, seen",
+                    item.from,
+                    item.subject,
+                    item.spamlevel
+                )
+            } else {
+                tr!(
+                    "From {0}, subject {1}, score {2}, unseen",
+                    item.from,
+                    item.subject,
+                    item.spamlevel
+                )
+            };
+            let is_selected = options.selected.as_deref() == Some(item.id.as_str());
+
             let mut main = Row::new()
                 .class(FlexFit)
                 .gap(1)
                 .padding_x(2)
                 .padding_y(1)
-                .border_bottom(true)
                 .class(AlignItems::Center)
-                .style("cursor", "pointer");
+                .style("cursor", "pointer")
+                .attribute("role", "button")
+                .attribute("tabindex", "0")
+                .attribute("aria-label", label)
+                .attribute("aria-current", is_selected.then_some("true"))
+                .onkeydown(button_keydown(open.clone()));
+            if is_selected {
+                main = main.class(ColorScheme::PrimaryContainer);
+            }
+            if focused {
+                main = main
+                    .attribute("id", FOCUSED_ROW_ID)
+                    .style("outline", "2px solid var(--pwt-color-primary)")
+                    .style("outline-offset", "-2px");
+            }
+            // only the row toggles the selection, the box just shows it
+            if selecting {
+                let checked = options.selection.contains(&item.id);
+                main = main
+                    .attribute("aria-pressed", if checked { "true" } else { "false" })
+                    .with_child(
+                        Fa::new(if checked {
+                            "check-square-o"
+                        } else {
+                            "square-o"
+                        })
+                        .class("pwt-font-title-large")
+                        .attribute("aria-hidden", "true"),
+                    );
+            }
             // dim seen mails and flag them with a leading marker
             if item.seen {
-                main = main.class(Opacity::Half).with_child(Fa::new("check"));
+                main = main
+                    .class(Opacity::Half)
+                    .with_child(Fa::new("check").attribute("aria-hidden", "true"));
             }
-            let main = main
-                .with_child(content)
-                .with_child(score)
-                .with_child(Fa::new("chevron-right").class(Opacity::Half));
-
-            let seen_action = if item.seen {
-                SlidableAction::new(tr!("Mark as Unseen"))
-                    .icon_class("fa fa-eye-slash")
-                    .on_activate(make_cb(MailAction::MarkUnseen))
-            } else {
-                SlidableAction::new(tr!("Mark as Seen"))
-                    .icon_class("fa fa-eye")
-                    .on_activate(make_cb(MailAction::MarkSeen))
-            };
+            let main = main.with_child(content).with_child(score);
 
-            Slidable::new(main)
-                .class(Overflow::Auto)
-                .on_tap({
-                    let id = item.id.clone();
-                    let on_preview = on_preview.clone();
-                    move |_| {
-                        if let Some(on_preview) = &on_preview {
-                            on_preview.emit(id.clone())
-                        }
+            let more = Button::new_icon("fa fa-ellipsis-v")
+                .class("circle")
+                .attribute("aria-label", tr!("More actions"))
+                .attribute("aria-haspopup", "menu")
+                .on_activate({
+                    let item = item.clone();
+                    link.callback(move |_| Msg::ShowActions(item.clone()))
+                });
+
+            // long-press on touch devices opens the context menu as well
+            let oncontextmenu = {
+                let item = item.clone();
+                link.callback(move |event: MouseEvent| {
+                    event.prevent_default();
+                    Msg::ShowActions(item.clone())
+                })
+            };
+            let on_long_press = {
+                let item = item.clone();
+                link.callback(move |()| Msg::ShowActions(item.clone()))
+            };
+            let on_tap = {
+                let long_press = options.long_press.clone();
+                Callback::from(move |()| {
+                    if !long_press.take_fired() {
+                        open.emit(());
                     }
                 })
-                .with_left_action(
-                    SlidableAction::new(tr!("Deliver"))
-                        .class(ColorScheme::SuccessContainer)
-                        .icon_class("fa fa-paper-plane")
-                        .on_activate(make_cb(MailAction::Deliver)),
-                )
-                .with_left_action(
-                    SlidableAction::new(tr!("Welcomelist"))
-                        .icon_class("fa fa-check")
-                        .on_activate(make_cb(MailAction::Welcomelist)),
-                )
-                .with_left_action(seen_action)
-                .with_right_action(
-                    SlidableAction::new(tr!("Blocklist"))
-                        .class(ColorScheme::WarningContainer)
-                        .icon_class("fa fa-times")
-                        .on_activate(make_cb(MailAction::Blocklist)),
-                )
-                .with_right_action(
-                    SlidableAction::new(tr!("Delete"))
-                        .class(ColorScheme::ErrorContainer)
-                        .icon_class("fa fa-trash")
-                        .on_activate(make_cb(MailAction::Delete)),
-                )
-                .into()
+            };
+
+            // without swipe actions the row is a plain button, the actions are
+            // still available via the "more" button and the context menu
+            let row: Html = if options.reduced_motion {
+                main.onclick(move |_| on_tap.emit(())).into()
+            } else {
+                let seen_action = if item.seen {
+                    MailAction::MarkUnseen
+                } else {
+                    MailAction::MarkSeen
+                };
+
+                Slidable::new(main)
+                    .class(Overflow::Auto)
+                    .class(FlexFit)
+                    .on_tap(move |_| on_tap.emit(()))
+                    .with_left_action(
+                        SlidableAction::new(MailAction::Deliver.label())
+                            .class(ColorScheme::SuccessContainer)
+                            .icon_class(MailAction::Deliver.icon_class())
+                            .on_activate(make_cb(MailAction::Deliver)),
+                    )
+                    .with_left_action(
+                        SlidableAction::new(MailAction::Welcomelist.label())
+                            .icon_class(MailAction::Welcomelist.icon_class())
+                            .on_activate(make_cb(MailAction::Welcomelist)),
+                    )
+                    .with_left_action(
+                        SlidableAction::new(seen_action.label())
+                            .icon_class(seen_action.icon_class())
+                            .on_activate(make_cb(seen_action)),
+                    )
+                    .with_right_action(
+                        SlidableAction::new(MailAction::Blocklist.label())
+                            .class(ColorScheme::WarningContainer)
+                            .icon_class(MailAction::Blocklist.icon_class())
+                            .on_activate(make_cb(MailAction::Blocklist)),
+                    )
+                    .with_right_action(
+                        SlidableAction::new(MailAction::Delete.label())
+                            .class(ColorScheme::ErrorContainer)
+                            .icon_class(MailAction::Delete.icon_class())
+                            .on_activate(make_cb(MailAction::Delete)),
+                    )
+                    .into()
+            };
+
+            let row = Row::new()
+                .class(AlignItems::Center)
+                .border_bottom(true)
+                // no callout of iOS for the long-press
+                .style("-webkit-touch-callout", "none")
+                .oncontextmenu(oncontextmenu)
+                .with_child(row)
+                .with_child(more);
+            options.long_press.attach(row, on_long_press).into()
         }
     }
 }
 
-fn epoch_to_date(epoch: i64) -> String {
-    let date = Date::new(&JsValue::from_f64(1000.0 * epoch as f64));
-    format!(
-        "{:04}-{:02}-{:02}",
-        date.get_full_year(),
-        date.get_month() + 1,
-        date.get_date()
-    )
+/// The bare, lower-cased address of a `From` header like `Name <user@example.com>`.
+pub(crate) fn sender_address(from: &str) -> String {
+    let address = match (from.rfind('<'), from.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &from[start + 1..end],
+        _ => from,
+    };
+    address.trim().to_lowercase()
+}
+
+/// The domain part of an address, or the whole address if it has none.
+pub(crate) fn sender_domain(address: &str) -> &str {
+    address
+        .rsplit_once('@')
+        .map_or(address, |(_, domain)| domain)
 }
 
-fn extract_mail_action_from_query_params() -> Result<Option<(String, MailAction)>, Error> {
+/// Checks a sender filter against a `From` header. A filter starting with `@`
+/// matches the whole domain, any other an exact address.
+pub(crate) fn sender_matches(filter: &str, from: &str) -> bool {
+    let address = sender_address(from);
+    match filter.strip_prefix('@') {
+        Some(domain) => sender_domain(&address).eq_ignore_ascii_case(domain),
+        None => address.eq_ignore_ascii_case(filter),
+    }
+}
+
+/// What a link of a spam report asks for.
+enum ReportLink {
+    View(String),               // id
+    Action(String, MailAction), // id
+}
+
+fn extract_report_link_from_query_params() -> Result<Option<ReportLink>, Error> {
     let id = extract_query_parameter("cselect")?;
     let action = extract_query_parameter("action")?;
 
-    if let (Some(id), Some(action)) = (id, action) {
-        let action = MailAction::from_str(&action)?;
-        return Ok(Some((id, action)));
+    match (id, action) {
+        (Some(id), Some(action)) => {
+            let action = MailAction::from_str(&action)?;
+            Ok(Some(ReportLink::Action(id, action)))
+        }
+        (Some(id), None) => Ok(Some(ReportLink::View(id))),
+        _ => Ok(None),
     }
-    Ok(None)
 }
 
 /// Removes `name` parameter from the get values via the browser `history` object and returns it
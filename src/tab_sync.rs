@@ -0,0 +1,126 @@
+use std::cell::RefCell;
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{BroadcastChannel, MessageEvent};
+
+use pwt::prelude::*;
+
+const CHANNEL_NAME: &str = "pmg-quarantine";
+
+/// What other tabs of the app need to know to stay consistent.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub(crate) enum TabEvent {
+    /// An action was run on the given mails.
+    MailAction {
+        ids: Vec<String>,
+        action: String,
+    },
+    /// The quarantine changed otherwise, e.g. a sender list.
+    Reload,
+    /// The settings in the local storage changed.
+    Settings,
+    Login {
+        userid: String,
+    },
+    Logout,
+}
+
+// A tab uses a single channel, as a channel also receives the messages of
+// other channels of the same name in the same tab.
+struct Channel {
+    channel: BroadcastChannel,
+    _listener: Closure<dyn Fn(MessageEvent)>,
+    subscribers: Vec<(usize, Callback<TabEvent>)>,
+    next_id: usize,
+}
+
+thread_local! {
+    static CHANNEL: RefCell<Option<Channel>> = RefCell::new(None);
+}
+
+fn with_channel<R>(f: impl FnOnce(&mut Channel) -> R) -> Option<R> {
+    CHANNEL.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        if cell.is_none() {
+            // not supported by all browsers, the tabs then simply do not sync
+            let channel = BroadcastChannel::new(CHANNEL_NAME)
+                .map_err(|err| log::error!("could not open broadcast channel: {err:?}"))
+                .ok()?;
+            let listener = Closure::<dyn Fn(MessageEvent)>::new(receive);
+            channel.set_onmessage(Some(listener.as_ref().unchecked_ref()));
+            *cell = Some(Channel {
+                channel,
+                _listener: listener,
+                subscribers: Vec::new(),
+                next_id: 0,
+            });
+        }
+        cell.as_mut().map(f)
+    })
+}
+
+fn receive(event: MessageEvent) {
+    let Some(data) = event.data().as_string() else {
+        return;
+    };
+    let event: TabEvent = match serde_json::from_str(&data) {
+        Ok(event) => event,
+        Err(err) => {
+            log::error!("invalid message from other tab: {err}");
+            return;
+        }
+    };
+    // emitted outside of the borrow, subscribers may broadcast themselves
+    let subscribers: Vec<_> = with_channel(|channel| {
+        channel
+            .subscribers
+            .iter()
+            .map(|(_, callback)| callback.clone())
+            .collect()
+    })
+    .unwrap_or_default();
+    for callback in subscribers {
+        callback.emit(event.clone());
+    }
+}
+
+/// Tells the other tabs of the app about `event`.
+pub(crate) fn broadcast(event: TabEvent) {
+    let data = match serde_json::to_string(&event) {
+        Ok(data) => data,
+        Err(err) => {
+            log::error!("could not serialize {event:?}: {err}");
+            return;
+        }
+    };
+    with_channel(|channel| {
+        if let Err(err) = channel.channel.post_message(&data.into()) {
+            log::error!("could not notify other tabs: {err:?}");
+        }
+    });
+}
+
+/// Receives the events of other tabs until dropped.
+pub(crate) struct TabSubscription(Option<usize>);
+
+impl TabSubscription {
+    pub fn new(callback: Callback<TabEvent>) -> Self {
+        Self(with_channel(|channel| {
+            let id = channel.next_id;
+            channel.next_id += 1;
+            channel.subscribers.push((id, callback));
+            id
+        }))
+    }
+}
+
+impl Drop for TabSubscription {
+    fn drop(&mut self) {
+        if let Some(id) = self.0 {
+            with_channel(|channel| channel.subscribers.retain(|(other, _)| *other != id));
+        }
+    }
+}